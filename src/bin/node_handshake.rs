@@ -1,18 +1,22 @@
-use std::error::Error;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
 use std::time::Duration;
 
 use clap::Parser;
-use near_network_primitives::types::RoutedMessageBody;
-use tokio::net::{TcpListener, TcpStream};
+use near_network_primitives::types::{PeerInfo, RoutedMessageBody};
+use tokio::join;
+use tokio::net::TcpListener;
 use tokio::sync::oneshot::Sender;
-use tokio::{join, pin};
 
 use node_handshake::config::Config;
+use node_handshake::connection::{OutboundQueue, PeerConnection};
+use node_handshake::types::disconnect::Disconnect;
+use node_handshake::types::envelope::Envelope;
+use node_handshake::types::handshake::Tier;
 use node_handshake::types::node::Node;
 use node_handshake::types::peer_message::PeerMessage;
-use node_handshake::{ReceivePeerMessage, SendPeerMessage};
+use node_handshake::types::peers::PeersRequest;
+use node_handshake::Error;
 
 async fn receive_handshake(
     listener_node: Arc<Node>,
@@ -30,72 +34,121 @@ async fn receive_handshake(
 
     loop {
         match listener.accept().await {
-            Ok((connection, from)) => {
+            Ok((stream, from)) => {
                 tokio::task::spawn({
                     let listener_node = listener_node.clone();
+                    let outbound_queue = OutboundQueue::from(&config);
                     async move {
-                        pin!(connection);
-                        let peer_message =
-                            connection.as_mut().receive_peer_message().await.unwrap();
-
-                        println!("<<< Receive from {from:#?} handshake {peer_message:#?}");
-                        let PeerMessage::Tier2Handshake(ref target_handshake) = peer_message else {
-                            unreachable!("first message is Handshake")
-                        };
-
-                        if listener_node.verify_handshake(target_handshake) {
-                            println!("<<< Handshake is valid");
-
-                            let sender_handshake = listener_node.create_handshake(
-                                target_handshake.sender_peer_id.clone(),
-                                target_handshake.partial_edge_info.nonce,
-                            );
-                            let peer_message = PeerMessage::Tier2Handshake(sender_handshake);
-                            println!(">>> Send to {from:?} handshake {peer_message:#?}");
-
-                            connection
-                                .as_mut()
-                                .send_peer_message(peer_message)
+                        let connection =
+                            match PeerConnection::accept_inbound(listener_node.clone(), stream)
                                 .await
-                                .unwrap();
-
-                            let peer_message = loop {
-                                match connection.as_mut().receive_peer_message().await {
-                                    Ok(peer_message) => break peer_message,
-                                    Err(_) => continue,
+                            {
+                                Ok(connection) => connection,
+                                Err(e) => {
+                                    println!(
+                                        "<<< Handshake from {from:?} failed: {e}, close connection"
+                                    );
+                                    return;
                                 }
                             };
 
-                            println!("<<< Receive from {from:?} ping {peer_message:?}");
+                        // Routing updates may arrive while a reply is waiting to be sent.
+                        let mut connection = connection.spawn(outbound_queue);
+                        let session = connection.session().clone();
+                        println!("<<< Handshake with {from:?} is established {session:#?}");
+                        if let Some(port) = connection.peer_listen_port() {
+                            listener_node.peer_table().insert_direct(PeerInfo {
+                                id: session.peer_id.clone(),
+                                addr: Some(SocketAddr::new(from.ip(), port)),
+                                account_id: None,
+                            });
+                        }
 
-                            let PeerMessage::Routed(routed_message) = peer_message else {
-                                unreachable!("only accept Ping message")
-                            };
-                            if routed_message.verify() {
-                                println!("<<< Ping is valid");
-
-                                let RoutedMessageBody::Ping(ref ping) = routed_message.msg.body
-                                else {
-                                    unreachable!("only accept Ping message")
-                                };
-                                let pong = listener_node
-                                    .create_pong(routed_message.author.clone(), ping.nonce);
-                                let peer_message = PeerMessage::Routed(pong.into());
-                                println!(">>> Send to {from:?} pong {peer_message:#?}");
-
-                                connection
-                                    .as_mut()
-                                    .send_peer_message(peer_message)
-                                    .await
-                                    .unwrap();
-                            } else {
-                                println!("<<< Ping is invalid, close connection");
-                                drop(connection);
+                        loop {
+                            // Replies carry the trace context of the request.
+                            let Envelope {
+                                message: peer_message,
+                                trace_context,
+                            } = match connection.receive_envelope().await {
+                                Ok(envelope) => envelope,
+                                Err(
+                                    e @ (Error::Io(_)
+                                    | Error::FrameTooLarge { .. }
+                                    | Error::ConnectionClosed),
+                                ) => {
+                                    println!("<<< {e}, close connection");
+                                    break;
+                                }
+                                Err(e) => {
+                                    println!("<<< Skip message from {from:?}: {e}");
+                                    continue;
+                                }
                             };
-                        } else {
-                            println!("<<< Handshake is invalid, close connection");
-                            drop(connection);
+
+                            match peer_message {
+                                PeerMessage::Routed(routed_message) => {
+                                    println!("<<< Receive from {from:?} ping {routed_message:?}");
+
+                                    let RoutedMessageBody::Ping(ref ping) = routed_message.msg.body
+                                    else {
+                                        println!("<<< Skip routed message, only accept Ping");
+                                        continue;
+                                    };
+                                    if !routed_message.verify() {
+                                        println!("<<< Ping is invalid, close connection");
+
+                                        let disconnect = Disconnect {
+                                            remove_from_connection_store: false,
+                                        };
+                                        println!(">>> Send to {from:?} {disconnect:?}");
+                                        let _ = connection.close(disconnect).await;
+                                        break;
+                                    }
+                                    println!("<<< Ping is valid");
+
+                                    let pong = listener_node
+                                        .create_pong(routed_message.msg.author.clone(), ping.nonce);
+                                    let peer_message = PeerMessage::Routed(Box::new(pong));
+                                    println!(">>> Send to {from:?} pong {peer_message:#?}");
+
+                                    if let Err(e) = connection.send_envelope(Envelope {
+                                        message: peer_message,
+                                        trace_context,
+                                    }) {
+                                        println!(">>> Send to {from:?} failed: {e}");
+                                    }
+                                }
+                                PeerMessage::PeersRequest(peers_request) => {
+                                    println!("<<< Receive from {from:?} {peers_request:?}");
+
+                                    let peers_response =
+                                        listener_node.peer_table().peers_response(&peers_request);
+                                    let peer_message = PeerMessage::PeersResponse(peers_response);
+                                    println!(">>> Send to {from:?} peers {peer_message:#?}");
+
+                                    if let Err(e) = connection.send_envelope(Envelope {
+                                        message: peer_message,
+                                        trace_context,
+                                    }) {
+                                        println!(">>> Send to {from:?} failed: {e}");
+                                    }
+                                }
+                                PeerMessage::Disconnect(disconnect) => {
+                                    println!(
+                                        "<<< Peer {from:?} disconnected {disconnect:?}, close connection"
+                                    );
+                                    break;
+                                }
+                                PeerMessage::Unknown { kind, .. } => {
+                                    println!("<<< Skip unknown message type {kind} from {from:?}");
+                                }
+                                peer_message => {
+                                    println!("<<< Skip {} from {from:?}", peer_message.kind());
+                                }
+                            }
                         }
+
+                        listener_node.peer_table().remove_direct(&session.peer_id);
                     }
                 });
             }
@@ -107,59 +160,144 @@ async fn receive_handshake(
 }
 
 async fn send_handshake(node: Arc<Node>, config: Config) {
-    let target_addr = config
-        .target_peer_info
-        .addr
-        .expect("Specify target peer info with ip address");
-    println!("Trying connect to {target_addr:?}");
-
-    let connection = tokio::time::timeout(Duration::from_secs(2), TcpStream::connect(target_addr))
-        .await
-        .expect("Timeout error")
-        .expect("Unable to connect");
-
-    let handshake = node.create_handshake(config.target_peer_info.id.clone(), 1);
+    // Validators connect to each other directly over TIER1.
+    let tier = if config.validator_key.is_some() {
+        Tier::Tier1
+    } else {
+        Tier::Tier2
+    };
+    println!(
+        "Trying connect to {:?} over {tier:?}",
+        config.target_peer_info
+    );
 
-    let peer_message = PeerMessage::Tier2Handshake(handshake);
-    println!(">>> Outbound send handshake {peer_message:#?}");
+    let mut connection = match PeerConnection::connect_outbound(
+        node.clone(),
+        &config.target_peer_info,
+        tier,
+    )
+    .await
+    {
+        Ok(connection) => connection,
+        Err(e) => {
+            println!("<<< Outbound handshake failed: {e}, close connection");
+            return;
+        }
+    };
 
-    pin!(connection);
+    println!(
+        "<<< Outbound handshake is established {:#?}",
+        connection.session()
+    );
+    node.peer_table()
+        .insert_direct(config.target_peer_info.clone());
 
-    connection
-        .as_mut()
-        .send_peer_message(peer_message)
-        .await
-        .unwrap();
+    let ping = node.create_ping(config.target_peer_info.id.clone());
+    let peer_message = PeerMessage::Routed(Box::new(ping));
+    println!(">>> Outbound send ping {peer_message:#?}");
 
-    let peer_message = connection.as_mut().receive_peer_message().await.unwrap();
+    if let Err(e) = connection.send(peer_message).await {
+        println!(">>> Outbound send failed: {e}, close connection");
+        return;
+    }
 
-    println!("<<< Outbound receive handshake {peer_message:#?}");
+    let peer_message = tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            match connection.receive().await {
+                Ok(peer_message) => break Ok(peer_message),
+                Err(e @ (Error::Io(_) | Error::FrameTooLarge { .. } | Error::ConnectionClosed)) => {
+                    break Err(e)
+                }
+                Err(_) => continue,
+            }
+        }
+    })
+    .await
+    .map_err(Error::from)
+    .and_then(|peer_message| peer_message);
+
+    match peer_message {
+        Ok(peer_message) => println!("<<< Outbound receive pong {peer_message:#?}"),
+        Err(e) => {
+            println!("<<< Outbound ping {e}, close connection");
+            return;
+        }
+    }
 
-    let ping = node.create_ping(config.target_peer_info.id.clone());
-    let peer_message = PeerMessage::Routed(ping.into());
-    println!(">>> Outbound send ping {peer_message:#?}");
+    let peer_message = PeerMessage::PeersRequest(PeersRequest::default());
+    println!(">>> Outbound send peers request {peer_message:#?}");
 
-    connection
-        .as_mut()
-        .send_peer_message(peer_message)
-        .await
-        .unwrap();
+    if let Err(e) = connection.send(peer_message).await {
+        println!(">>> Outbound send failed: {e}, close connection");
+        return;
+    }
 
-    let peer_message = loop {
-        match connection.as_mut().receive_peer_message().await {
-            Ok(peer_message) => break peer_message,
-            Err(_) => continue,
+    let peer_message = tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            match connection.receive().await {
+                Ok(peer_message @ (PeerMessage::PeersResponse(_) | PeerMessage::Disconnect(_))) => {
+                    break Ok(peer_message)
+                }
+                Ok(peer_message) => println!("<<< Outbound skip {}", peer_message.kind()),
+                Err(e @ (Error::Io(_) | Error::FrameTooLarge { .. } | Error::ConnectionClosed)) => {
+                    break Err(e)
+                }
+                Err(_) => continue,
+            }
+        }
+    })
+    .await
+    .map_err(Error::from)
+    .and_then(|peer_message| peer_message);
+
+    match peer_message {
+        Ok(PeerMessage::Disconnect(disconnect)) => {
+            println!("<<< Outbound peer disconnected {disconnect:?}, close connection");
+            return;
+        }
+        Ok(PeerMessage::PeersResponse(peers_response)) => {
+            println!("<<< Outbound receive peers {peers_response:#?}");
+
+            let mut peer_table = node.peer_table();
+            for peer_info in peers_response
+                .peers
+                .into_iter()
+                .chain(peers_response.direct_peers)
+                .filter(|peer_info| peer_info.id != node.peer_id())
+            {
+                peer_table.insert(peer_info);
+            }
         }
+        Ok(peer_message) => {
+            println!(
+                "<<< Outbound unexpected {}, close connection",
+                peer_message.kind()
+            );
+            return;
+        }
+        Err(e @ Error::Timeout(_)) => println!("<<< Outbound peers request {e}"),
+        Err(e) => {
+            println!("<<< {e}, close connection");
+            return;
+        }
+    }
+
+    // Probe is over, the peer shouldn't record it as a failed connection.
+    let disconnect = Disconnect {
+        remove_from_connection_store: true,
     };
+    println!(">>> Outbound send {disconnect:?}");
 
-    println!("<<< Outbound receive pong {peer_message:#?}");
+    if let Err(e) = connection.close(disconnect).await {
+        println!(">>> Outbound close failed: {e}");
+    }
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config = Config::parse();
 
-    let sender_node = Arc::new(Node::from(config.clone()));
+    let sender_node = Arc::new(Node::try_from(config.clone())?);
     println!(
         "My node id {}@127.0.0.1:{}",
         sender_node.peer_id(),
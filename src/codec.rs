@@ -0,0 +1,323 @@
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::error::Error;
+use crate::types::envelope::Envelope;
+use crate::types::peer_message::PeerMessage;
+
+/// Size of the little endian `u32` length prefix of every frame.
+const LENGTH_PREFIX_SIZE: usize = std::mem::size_of::<u32>();
+
+/// Maximum size of a single network message accepted by nearcore.
+pub const NETWORK_MESSAGE_MAX_SIZE_BYTES: usize = 512 * 1024 * 1024;
+
+/// Codec for the NEAR wire format: `<len(M)>` as little endian `u32` followed by
+/// `M`, the protobuf encoded `PeerMessage`.
+///
+/// Wrap a stream with `tokio_util::codec::Framed` to get a `Stream + Sink` of `PeerMessage`.
+/// Frames bigger than `max_frame_size` are rejected as soon as the length prefix is read.
+#[derive(Debug, Clone, Copy)]
+pub struct PeerMessageCodec {
+    max_frame_size: usize,
+}
+
+impl PeerMessageCodec {
+    pub fn new(max_frame_size: usize) -> Self {
+        Self { max_frame_size }
+    }
+
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+}
+
+impl Default for PeerMessageCodec {
+    fn default() -> Self {
+        Self::new(NETWORK_MESSAGE_MAX_SIZE_BYTES)
+    }
+}
+
+impl PeerMessageCodec {
+    /// Splits the protobuf encoded message of the next frame off `src`.
+    fn decode_frame(&self, src: &mut BytesMut) -> Result<Option<BytesMut>, Error> {
+        if src.len() < LENGTH_PREFIX_SIZE {
+            return Ok(None);
+        }
+
+        let mut length_prefix = [0u8; LENGTH_PREFIX_SIZE];
+        length_prefix.copy_from_slice(&src[..LENGTH_PREFIX_SIZE]);
+        let message_size = u32::from_le_bytes(length_prefix) as usize;
+        if message_size > self.max_frame_size {
+            return Err(Error::FrameTooLarge {
+                frame_size: message_size,
+                max_frame_size: self.max_frame_size,
+            });
+        }
+
+        let frame_size = LENGTH_PREFIX_SIZE + message_size;
+        if src.len() < frame_size {
+            src.reserve(frame_size - src.len());
+            return Ok(None);
+        }
+
+        src.advance(LENGTH_PREFIX_SIZE);
+        Ok(Some(src.split_to(message_size)))
+    }
+
+    /// Writes the protobuf encoded `message` to `dst` as a single frame.
+    fn encode_frame(&self, message: Vec<u8>, dst: &mut BytesMut) -> Result<(), Error> {
+        if message.len() > self.max_frame_size {
+            return Err(Error::FrameTooLarge {
+                frame_size: message.len(),
+                max_frame_size: self.max_frame_size,
+            });
+        }
+        let message_size = u32::try_from(message.len()).map_err(|_| Error::FrameTooLarge {
+            frame_size: message.len(),
+            max_frame_size: u32::MAX as usize,
+        })?;
+
+        dst.reserve(LENGTH_PREFIX_SIZE + message.len());
+        dst.put_u32_le(message_size);
+        dst.put_slice(&message);
+
+        Ok(())
+    }
+}
+
+impl Decoder for PeerMessageCodec {
+    type Item = PeerMessage;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode_frame(src)? {
+            Some(frame) => PeerMessage::parse_from_bytes(&frame).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder<PeerMessage> for PeerMessageCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: PeerMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode_frame(item.write_to_bytes()?, dst)
+    }
+}
+
+/// Same wire format as `PeerMessageCodec`, but keeps the trace context of the messages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvelopeCodec(PeerMessageCodec);
+
+impl EnvelopeCodec {
+    pub fn new(max_frame_size: usize) -> Self {
+        Self(PeerMessageCodec::new(max_frame_size))
+    }
+
+    pub fn max_frame_size(&self) -> usize {
+        self.0.max_frame_size()
+    }
+}
+
+impl Decoder for EnvelopeCodec {
+    type Item = Envelope;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.0.decode_frame(src)? {
+            Some(frame) => Envelope::parse_from_bytes(&frame).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder<Envelope> for EnvelopeCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: Envelope, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.0.encode_frame(item.write_to_bytes()?, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::{BufMut, BytesMut};
+    use futures::{SinkExt, StreamExt};
+    use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, SecretKey};
+    use near_network_primitives::types::{PartialEdgeInfo, PeerChainInfoV2};
+    use near_primitives::block::GenesisId;
+    use near_primitives::borsh::BorshDeserialize;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::network::PeerId;
+    use opentelemetry::trace::{SpanId, TraceId};
+    use rand::rngs::OsRng;
+    use tokio::io::AsyncWriteExt;
+    use tokio_util::codec::{Decoder, Encoder, Framed};
+
+    use crate::codec::{EnvelopeCodec, PeerMessageCodec};
+    use crate::error::Error;
+    use crate::types::envelope::Envelope;
+    use crate::types::handshake::Handshake;
+    use crate::types::peer_message::PeerMessage;
+    use crate::types::trace_context::TraceContext;
+    use crate::ReceivePeerMessage;
+
+    fn handshake_message() -> Result<PeerMessage> {
+        let genesis_id = GenesisId {
+            chain_id: "testnet".to_string(),
+            hash: CryptoHash::try_from_slice(
+                ed25519_dalek::SecretKey::generate(&mut OsRng).as_bytes(),
+            )?,
+        };
+        let sender_chain_info = PeerChainInfoV2 {
+            genesis_id,
+            height: 0,
+            tracked_shards: vec![],
+            archival: false,
+        };
+        let keypair = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let sender_peer_id = PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+            keypair.public.to_bytes(),
+        )));
+        let target_peer_id = PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+            ed25519_dalek::Keypair::generate(&mut OsRng)
+                .public
+                .to_bytes(),
+        )));
+        let secret_key = SecretKey::ED25519(ED25519SecretKey(keypair.to_bytes()));
+        let partial_edge_info =
+            PartialEdgeInfo::new(&sender_peer_id, &target_peer_id, 1, &secret_key);
+
+        Ok(PeerMessage::Tier2Handshake(Handshake {
+            protocol_version: 63,
+            oldest_supported_version: 61,
+            sender_peer_id,
+            target_peer_id,
+            sender_listen_port: Some(51200),
+            sender_chain_info,
+            partial_edge_info,
+            owned_account: None,
+        }))
+    }
+
+    #[test]
+    fn test_decode_partial_frame() -> Result<()> {
+        let peer_message = handshake_message()?;
+
+        let mut encoded = BytesMut::new();
+        PeerMessageCodec::default()
+            .encode(peer_message.clone(), &mut encoded)
+            .unwrap();
+
+        let mut codec = PeerMessageCodec::default();
+        let mut buf = BytesMut::new();
+        for byte in &encoded[..encoded.len() - 1] {
+            buf.extend_from_slice(&[*byte]);
+            assert!(codec.decode(&mut buf).unwrap().is_none());
+        }
+        buf.extend_from_slice(&encoded[encoded.len() - 1..]);
+
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(peer_message));
+        assert!(buf.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_several_frames() -> Result<()> {
+        let (first, second) = (handshake_message()?, handshake_message()?);
+
+        let mut codec = PeerMessageCodec::default();
+        let mut buf = BytesMut::new();
+        codec.encode(first.clone(), &mut buf).unwrap();
+        codec.encode(second.clone(), &mut buf).unwrap();
+
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(first));
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(second));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_framed() -> Result<()> {
+        let peer_message = handshake_message()?;
+
+        let (client, server) = tokio::io::duplex(64);
+        let mut client = Framed::new(client, PeerMessageCodec::default());
+        let mut server = Framed::new(server, PeerMessageCodec::default());
+
+        let (sent, received) = tokio::join!(client.send(peer_message.clone()), server.next());
+        assert!(sent.is_ok());
+        assert_eq!(peer_message, received.unwrap().unwrap());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_framed_envelope() -> Result<()> {
+        let envelope = Envelope {
+            message: handshake_message()?,
+            trace_context: Some(TraceContext {
+                trace_id: TraceId::from_bytes(rand::random()),
+                span_id: SpanId::from_bytes(rand::random()),
+                sampling_priority: None,
+            }),
+        };
+
+        let (client, server) = tokio::io::duplex(64);
+        let mut client = Framed::new(client, EnvelopeCodec::default());
+        let mut server = Framed::new(server, PeerMessageCodec::default());
+
+        let (sent, received) = tokio::join!(client.send(envelope.clone()), server.next());
+        assert!(sent.is_ok());
+        assert_eq!(envelope.message, received.unwrap().unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_frame_too_large() {
+        let mut codec = PeerMessageCodec::new(1024);
+        let mut buf = BytesMut::new();
+        buf.put_u32_le(u32::MAX);
+
+        let error = codec.decode(&mut buf).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::FrameTooLarge {
+                frame_size,
+                max_frame_size: 1024,
+            } if frame_size == u32::MAX as usize
+        ));
+        assert!(buf.capacity() < 1024);
+    }
+
+    #[test]
+    fn test_encode_frame_too_large() -> Result<()> {
+        let mut codec = PeerMessageCodec::new(16);
+        let mut buf = BytesMut::new();
+
+        let error = codec.encode(handshake_message()?, &mut buf).unwrap_err();
+        assert!(matches!(error, Error::FrameTooLarge { .. }));
+        assert!(buf.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_receive_frame_too_large() -> Result<()> {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        // Only the length prefix is written, reading the body would never complete.
+        client.write_u32_le(u32::MAX).await?;
+
+        let error = server
+            .receive_peer_message_with(&mut PeerMessageCodec::new(1024))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::FrameTooLarge { .. }));
+
+        Ok(())
+    }
+}
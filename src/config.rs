@@ -1,11 +1,15 @@
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use clap::builder::PossibleValue;
 use clap::{Parser, ValueEnum};
-use near_network_primitives::types::{PeerChainInfoV2, PeerInfo};
+use near_network_primitives::types::{PeerChainInfoV2, PeerInfo, ROUTED_MESSAGE_TTL};
 use near_primitives::block::GenesisId;
 use near_primitives::hash::CryptoHash;
 
+use crate::codec::NETWORK_MESSAGE_MAX_SIZE_BYTES;
+
 #[derive(Debug, Clone, Copy)]
 pub enum Network {
     Localnet,
@@ -44,6 +48,31 @@ impl FromStr for Network {
     }
 }
 
+/// What a connection does when its outbound queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drops the message, the connection stays open.
+    Drop,
+    /// Closes the connection, the peer is too slow to keep up.
+    Disconnect,
+}
+
+impl ValueEnum for OverflowPolicy {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Drop, Self::Disconnect]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            OverflowPolicy::Drop => PossibleValue::new("drop").help("Drop the message"),
+            OverflowPolicy::Disconnect => {
+                PossibleValue::new("disconnect").help("Close the connection")
+            }
+        }
+        .into()
+    }
+}
+
 impl From<Network> for GenesisId {
     fn from(value: Network) -> Self {
         match value {
@@ -96,4 +125,22 @@ pub struct Config {
     pub oldest_supported_version: u32,
     #[arg(long, default_value_t = 34567)]
     pub sender_listen_port: u16,
+    /// Maximum size of a message accepted from peers, larger frames are rejected.
+    #[arg(long, default_value_t = NETWORK_MESSAGE_MAX_SIZE_BYTES)]
+    pub max_frame_size: usize,
+    /// TTL of routed messages created by the node, i.e. the maximum number of hops.
+    #[arg(long, default_value_t = ROUTED_MESSAGE_TTL, value_parser = clap::value_parser!(u8).range(1..))]
+    pub routed_message_ttl: u8,
+    /// Messages waiting to be written to a peer before the overflow policy applies.
+    #[arg(long, default_value = "64")]
+    pub outbound_queue_size: NonZeroUsize,
+    /// What to do with a message for a peer whose outbound queue is full.
+    #[arg(long, default_value = "drop")]
+    pub outbound_queue_overflow: OverflowPolicy,
+    /// nearcore `node_key.json`, generated if missing.
+    #[arg(long)]
+    pub node_key: Option<PathBuf>,
+    /// nearcore `validator_key.json`, proves the validator account in handshakes.
+    #[arg(long)]
+    pub validator_key: Option<PathBuf>,
 }
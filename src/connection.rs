@@ -0,0 +1,823 @@
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use near_network_primitives::types::PeerInfo;
+use near_primitives::network::PeerId;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::task::JoinHandle;
+
+use crate::codec::EnvelopeCodec;
+use crate::config::{Config, OverflowPolicy};
+use crate::error::{ConnectionError, Error, HandshakeError};
+use crate::types::disconnect::Disconnect;
+use crate::types::envelope::Envelope;
+use crate::types::handshake::Tier;
+use crate::types::node::Node;
+use crate::types::peer_message::PeerMessage;
+use crate::types::session::Session;
+use crate::{ReceivePeerMessage, SendPeerMessage};
+
+/// Time for the peer to conclude the handshake, including connecting to it.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Time for `PeerConnectionHandle::close` to flush the queue and send `Disconnect`.
+pub const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Lifecycle of a `PeerConnection`.
+///
+/// Outbound connections go through every state, inbound ones skip `HandshakeSent`
+/// since the handshake of the peer arrives first.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ConnectionState {
+    /// Stream is open, no handshake is exchanged yet.
+    Connecting,
+    /// Own handshake is sent, waiting for the one of the peer.
+    HandshakeSent,
+    /// Handshakes are exchanged and verified, other messages may be sent.
+    Established,
+    /// `Disconnect` is sent or received, the stream is about to be closed.
+    Closing,
+}
+
+/// Connection to a peer of `node`, returned by the constructors only once the
+/// handshake is concluded.
+#[derive(Debug)]
+pub struct PeerConnection<S = TcpStream> {
+    node: Arc<Node>,
+    stream: S,
+    codec: EnvelopeCodec,
+    state: ConnectionState,
+    /// Set when the connection is established.
+    session: Option<Session>,
+    peer_listen_port: Option<u16>,
+}
+
+impl PeerConnection<TcpStream> {
+    /// Connects to `peer_info` and runs the handshake of `tier` as the initiator.
+    pub async fn connect_outbound(
+        node: Arc<Node>,
+        peer_info: &PeerInfo,
+        tier: Tier,
+    ) -> Result<Self, ConnectionError> {
+        let addr = peer_info
+            .addr
+            .ok_or_else(|| ConnectionError::MissingAddress(peer_info.id.clone()))?;
+
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, async {
+            let stream = TcpStream::connect(addr).await.map_err(Error::from)?;
+            Self::outbound(node, peer_info.id.clone(), tier, stream).await
+        })
+        .await
+        .map_err(Error::from)?
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> PeerConnection<S> {
+    fn new(node: Arc<Node>, stream: S) -> Self {
+        Self {
+            codec: node.codec(),
+            node,
+            stream,
+            state: ConnectionState::Connecting,
+            session: None,
+            peer_listen_port: None,
+        }
+    }
+
+    /// Runs the handshake of `tier` with `peer_id` as the initiator over an open `stream`.
+    pub async fn handshake_outbound(
+        node: Arc<Node>,
+        peer_id: PeerId,
+        tier: Tier,
+        stream: S,
+    ) -> Result<Self, ConnectionError> {
+        tokio::time::timeout(
+            HANDSHAKE_TIMEOUT,
+            Self::outbound(node, peer_id, tier, stream),
+        )
+        .await
+        .map_err(Error::from)?
+    }
+
+    /// Waits for the handshake of the peer on an accepted `stream` and answers it
+    /// in the same tier. Invalid handshakes are answered with `HandshakeFailure`.
+    pub async fn accept_inbound(node: Arc<Node>, stream: S) -> Result<Self, ConnectionError> {
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, Self::inbound(node, stream))
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn outbound(
+        node: Arc<Node>,
+        peer_id: PeerId,
+        tier: Tier,
+        stream: S,
+    ) -> Result<Self, ConnectionError> {
+        let mut connection = Self::new(node.clone(), stream);
+
+        let nonce = node.create_nonce(&peer_id);
+        let sent = node.create_handshake(peer_id, nonce);
+        connection
+            .send(PeerMessage::handshake(tier, sent.clone()))
+            .await?;
+        connection.state = ConnectionState::HandshakeSent;
+
+        let response = connection.receive().await?;
+        let session = node.verify_handshake_response(tier, &sent, &response)?;
+        let peer_listen_port = match response {
+            PeerMessage::Tier1Handshake(received) | PeerMessage::Tier2Handshake(received) => {
+                received.sender_listen_port
+            }
+            _ => None,
+        };
+
+        connection.establish(session, peer_listen_port);
+        Ok(connection)
+    }
+
+    async fn inbound(node: Arc<Node>, stream: S) -> Result<Self, ConnectionError> {
+        let mut connection = Self::new(node.clone(), stream);
+
+        let (tier, received) = match connection.receive().await? {
+            PeerMessage::Tier1Handshake(handshake) => (Tier::Tier1, handshake),
+            PeerMessage::Tier2Handshake(handshake) => (Tier::Tier2, handshake),
+            peer_message => {
+                return Err(HandshakeError::UnexpectedMessage(peer_message.kind()).into())
+            }
+        };
+
+        if !node.verify_handshake_signature(&received) {
+            return Err(HandshakeError::InvalidSignature.into());
+        }
+
+        match tier {
+            Tier::Tier1 => node.verify_tier1_handshake(&received)?,
+            Tier::Tier2 => node.verify_owned_account(&received)?,
+        }
+
+        let session = match node.verify_handshake(&received) {
+            Ok(session) => session,
+            Err(reason) => {
+                connection
+                    .send(PeerMessage::HandshakeFailure(
+                        node.peer_info(),
+                        reason.clone(),
+                    ))
+                    .await?;
+                return Err(HandshakeError::Invalid(reason).into());
+            }
+        };
+
+        node.accept_nonce(&received.sender_peer_id, received.partial_edge_info.nonce)?;
+
+        let response = node.create_handshake(
+            received.sender_peer_id.clone(),
+            received.partial_edge_info.nonce,
+        );
+        connection
+            .send(PeerMessage::handshake(tier, response))
+            .await?;
+
+        connection.establish(session, received.sender_listen_port);
+        Ok(connection)
+    }
+
+    fn establish(&mut self, session: Session, peer_listen_port: Option<u16>) {
+        self.state = ConnectionState::Established;
+        self.session = Some(session);
+        self.peer_listen_port = peer_listen_port;
+    }
+
+    pub fn node(&self) -> &Arc<Node> {
+        &self.node
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Parameters negotiated in the handshake.
+    pub fn session(&self) -> &Session {
+        self.session
+            .as_ref()
+            .expect("constructors return established connections")
+    }
+
+    /// Port the peer accepts connections on, if it announced one.
+    pub fn peer_listen_port(&self) -> Option<u16> {
+        self.peer_listen_port
+    }
+
+    /// Sends the message with the trace context of the current span.
+    pub async fn send(&mut self, peer_message: PeerMessage) -> Result<(), Error> {
+        self.stream
+            .send_peer_message_with(Envelope::from(peer_message), &mut self.codec)
+            .await
+    }
+
+    /// Receives the next message, `Disconnect` moves the connection to `Closing`.
+    pub async fn receive(&mut self) -> Result<PeerMessage, Error> {
+        Ok(self.receive_envelope().await?.message)
+    }
+
+    /// Same as `receive`, but keeps the trace context of the sender.
+    pub async fn receive_envelope(&mut self) -> Result<Envelope, Error> {
+        let envelope = self
+            .stream
+            .receive_peer_message_with(&mut self.codec)
+            .await?;
+
+        if let PeerMessage::Disconnect(_) = envelope.message {
+            self.state = ConnectionState::Closing;
+        }
+
+        Ok(envelope)
+    }
+
+    /// Sends `Disconnect` and shuts the stream down.
+    pub async fn close(&mut self, disconnect: Disconnect) -> Result<(), Error> {
+        self.state = ConnectionState::Closing;
+        self.send(PeerMessage::Disconnect(disconnect)).await?;
+        self.stream.shutdown().await?;
+
+        Ok(())
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send + 'static> PeerConnection<S> {
+    /// Splits the connection into a reader task, which emits the received messages,
+    /// and a writer task, which sends the messages queued with `PeerConnectionHandle::send`.
+    pub fn spawn(self, outbound_queue: OutboundQueue) -> PeerConnectionHandle {
+        let session = self.session().clone();
+        let Self {
+            node,
+            stream,
+            mut codec,
+            state,
+            peer_listen_port,
+            ..
+        } = self;
+
+        let (mut read_half, mut write_half) = tokio::io::split(stream);
+        let mut write_codec = codec;
+        // The inbound channel needs no bound of its own: once it is full the reader task
+        // waits for `PeerConnectionHandle::receive`, stops reading the stream and TCP flow
+        // control slows the peer down. Unlike the outbound queue nothing is dropped.
+        let (inbound_sender, inbound) = mpsc::channel(outbound_queue.capacity.get());
+        let (outbound, mut outbound_receiver) =
+            mpsc::channel::<Envelope>(outbound_queue.capacity.get());
+
+        let reader = tokio::spawn(async move {
+            loop {
+                let received = read_half.receive_peer_message_with(&mut codec).await;
+                let last = matches!(
+                    received,
+                    Ok(Envelope {
+                        message: PeerMessage::Disconnect(_),
+                        ..
+                    }) | Err(Error::Io(_) | Error::FrameTooLarge { .. })
+                );
+                if inbound_sender.send(received).await.is_err() || last {
+                    break;
+                }
+            }
+        });
+
+        let writer = tokio::spawn(async move {
+            while let Some(envelope) = outbound_receiver.recv().await {
+                let last = matches!(envelope.message, PeerMessage::Disconnect(_));
+                let sent = write_half
+                    .send_peer_message_with(envelope, &mut write_codec)
+                    .await;
+                if sent.is_err() || last {
+                    break;
+                }
+            }
+            let _ = write_half.shutdown().await;
+        });
+
+        PeerConnectionHandle {
+            node,
+            state,
+            session,
+            peer_listen_port,
+            overflow_policy: outbound_queue.overflow_policy,
+            inbound,
+            outbound,
+            reader,
+            writer,
+        }
+    }
+}
+
+/// Bound of the messages waiting for the writer task of a `PeerConnectionHandle`.
+/// The received messages waiting for `PeerConnectionHandle::receive` share the capacity,
+/// but never overflow, the reader task waits instead.
+#[derive(Debug, Clone, Copy)]
+pub struct OutboundQueue {
+    pub capacity: NonZeroUsize,
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl From<&Config> for OutboundQueue {
+    fn from(value: &Config) -> Self {
+        Self {
+            capacity: value.outbound_queue_size,
+            overflow_policy: value.outbound_queue_overflow,
+        }
+    }
+}
+
+/// Established connection driven by a reader and a writer task, see `PeerConnection::spawn`.
+#[derive(Debug)]
+pub struct PeerConnectionHandle {
+    node: Arc<Node>,
+    state: ConnectionState,
+    session: Session,
+    peer_listen_port: Option<u16>,
+    overflow_policy: OverflowPolicy,
+    inbound: mpsc::Receiver<Result<Envelope, Error>>,
+    outbound: mpsc::Sender<Envelope>,
+    reader: JoinHandle<()>,
+    writer: JoinHandle<()>,
+}
+
+impl PeerConnectionHandle {
+    pub fn node(&self) -> &Arc<Node> {
+        &self.node
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Parameters negotiated in the handshake.
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Port the peer accepts connections on, if it announced one.
+    pub fn peer_listen_port(&self) -> Option<u16> {
+        self.peer_listen_port
+    }
+
+    /// Queues the message with the trace context of the current span, see `send_envelope`.
+    pub fn send(&mut self, peer_message: PeerMessage) -> Result<(), Error> {
+        self.send_envelope(Envelope::from(peer_message))
+    }
+
+    /// Queues the envelope for the writer task without waiting for it to be sent.
+    /// When the queue is full the envelope is dropped, `OverflowPolicy::Disconnect`
+    /// also closes the connection.
+    pub fn send_envelope(&mut self, envelope: Envelope) -> Result<(), Error> {
+        if self.state == ConnectionState::Closing {
+            return Err(Error::ConnectionClosed);
+        }
+
+        match self.outbound.try_send(envelope) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                if self.overflow_policy == OverflowPolicy::Disconnect {
+                    self.reader.abort();
+                    self.writer.abort();
+                    self.state = ConnectionState::Closing;
+                }
+                Err(Error::QueueFull)
+            }
+            Err(TrySendError::Closed(_)) => {
+                self.state = ConnectionState::Closing;
+                Err(Error::ConnectionClosed)
+            }
+        }
+    }
+
+    /// Next message emitted by the reader task, `Disconnect` moves the connection to `Closing`.
+    pub async fn receive(&mut self) -> Result<PeerMessage, Error> {
+        Ok(self.receive_envelope().await?.message)
+    }
+
+    /// Same as `receive`, but keeps the trace context of the sender.
+    pub async fn receive_envelope(&mut self) -> Result<Envelope, Error> {
+        let Some(received) = self.inbound.recv().await else {
+            self.state = ConnectionState::Closing;
+            return Err(Error::ConnectionClosed);
+        };
+
+        if let Ok(Envelope {
+            message: PeerMessage::Disconnect(_),
+            ..
+        }) = received
+        {
+            self.state = ConnectionState::Closing;
+        }
+
+        received
+    }
+
+    /// Sends `Disconnect` after the queued messages and waits for the writer task
+    /// to shut the stream down. Gives up after `CLOSE_TIMEOUT` when the peer stops
+    /// reading, the stream is then closed without `Disconnect`.
+    pub async fn close(mut self, disconnect: Disconnect) -> Result<(), Error> {
+        self.reader.abort();
+
+        let (outbound, writer) = (&self.outbound, &mut self.writer);
+        tokio::time::timeout(CLOSE_TIMEOUT, async move {
+            let sent = outbound
+                .send(Envelope::from(PeerMessage::Disconnect(disconnect)))
+                .await
+                .map_err(|_| Error::ConnectionClosed);
+            // The writer task stops after `Disconnect`.
+            let _ = writer.await;
+
+            sent
+        })
+        .await?
+    }
+}
+
+/// Dropping the handle stops both tasks, which closes the stream.
+impl Drop for PeerConnectionHandle {
+    fn drop(&mut self) {
+        self.reader.abort();
+        self.writer.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use near_crypto::{KeyType, SecretKey};
+    use near_primitives::hash::CryptoHash;
+    use opentelemetry::trace::{SpanId, TraceId};
+    use tokio::io::DuplexStream;
+
+    use crate::config::{Network, OverflowPolicy};
+    use crate::connection::{ConnectionState, OutboundQueue, PeerConnection};
+    use crate::error::{ConnectionError, Error, HandshakeError};
+    use crate::types::disconnect::Disconnect;
+    use crate::types::envelope::Envelope;
+    use crate::types::handshake::Tier;
+    use crate::types::handshake_failure::HandshakeFailureReason;
+    use crate::types::node::Node;
+    use crate::types::peer_message::PeerMessage;
+    use crate::types::trace_context::{SamplingPriority, TraceContext};
+
+    type Connection = PeerConnection<DuplexStream>;
+
+    /// Runs the handshake of `tier` between the nodes over a duplex stream.
+    async fn handshake_pair(
+        sender_node: Arc<Node>,
+        target_node: Arc<Node>,
+        tier: Tier,
+    ) -> (
+        Result<Connection, ConnectionError>,
+        Result<Connection, ConnectionError>,
+    ) {
+        let (sender, target) = tokio::io::duplex(4096);
+
+        tokio::join!(
+            PeerConnection::handshake_outbound(sender_node, target_node.peer_id(), tier, sender),
+            PeerConnection::accept_inbound(target_node, target),
+        )
+    }
+
+    /// Outbound and inbound connection between two new localnet nodes.
+    async fn connected_pair() -> Result<(Connection, Connection)> {
+        let (outbound, inbound) = handshake_pair(
+            Arc::new(Node::new_test(Network::Localnet.into())),
+            Arc::new(Node::new_test(Network::Localnet.into())),
+            Tier::Tier2,
+        )
+        .await;
+
+        Ok((outbound?, inbound?))
+    }
+
+    #[tokio::test]
+    async fn test_handshake() -> Result<()> {
+        let (mut outbound, mut inbound) = connected_pair().await?;
+
+        assert_eq!(outbound.state(), ConnectionState::Established);
+        assert_eq!(inbound.state(), ConnectionState::Established);
+        assert_eq!(outbound.session().peer_id, inbound.node().peer_id());
+        assert_eq!(inbound.session().peer_id, outbound.node().peer_id());
+        assert_eq!(outbound.session().protocol_version, 63);
+        assert_eq!(outbound.session().edge, inbound.session().edge);
+        assert_eq!(inbound.peer_listen_port(), Some(34567));
+
+        outbound.close(Disconnect::default()).await?;
+        assert_eq!(outbound.state(), ConnectionState::Closing);
+        assert_eq!(
+            inbound.receive().await?,
+            PeerMessage::Disconnect(Disconnect::default())
+        );
+        assert_eq!(inbound.state(), ConnectionState::Closing);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handshake_again() -> Result<()> {
+        let (sender_node, target_node) = (
+            Arc::new(Node::new_test(Network::Localnet.into())),
+            Arc::new(Node::new_test(Network::Localnet.into())),
+        );
+
+        for _ in 0..2 {
+            let (outbound, inbound) =
+                handshake_pair(sender_node.clone(), target_node.clone(), Tier::Tier2).await;
+            assert_eq!(outbound?.session().edge, inbound?.session().edge);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handshake_after_failure() -> Result<()> {
+        let (sender_node, target_node) = (
+            Arc::new(Node::new_test(Network::Localnet.into())),
+            Arc::new(Node::new_test(Network::Localnet.into())),
+        );
+
+        // Rejected since the sender isn't a validator.
+        let (_, inbound) =
+            handshake_pair(sender_node.clone(), target_node.clone(), Tier::Tier1).await;
+        assert!(inbound.is_err());
+
+        // The nonce of the failed handshake doesn't shadow the one of the target.
+        let (outbound, inbound) = handshake_pair(target_node, sender_node, Tier::Tier2).await;
+        assert_eq!(outbound?.session().edge, inbound?.session().edge);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handshake_rejected() -> Result<()> {
+        let (outbound, inbound) = handshake_pair(
+            Arc::new(Node::new_test(Network::Testnet.into())),
+            Arc::new(Node::new_test(Network::Localnet.into())),
+            Tier::Tier2,
+        )
+        .await;
+
+        assert!(matches!(
+            outbound.unwrap_err(),
+            ConnectionError::Handshake(HandshakeError::Rejected(
+                HandshakeFailureReason::GenesisMismatch(_)
+            ))
+        ));
+        assert!(matches!(
+            inbound.unwrap_err(),
+            ConnectionError::Handshake(HandshakeError::Invalid(
+                HandshakeFailureReason::GenesisMismatch(_)
+            ))
+        ));
+
+        let (sender_node, target_node, other_node) = (
+            Arc::new(Node::new_test(Network::Localnet.into())),
+            Arc::new(Node::new_test(Network::Localnet.into())),
+            Arc::new(Node::new_test(Network::Localnet.into())),
+        );
+        let (sender, target) = tokio::io::duplex(4096);
+
+        let (outbound, inbound) = tokio::join!(
+            PeerConnection::handshake_outbound(
+                sender_node,
+                other_node.peer_id(),
+                Tier::Tier2,
+                sender
+            ),
+            PeerConnection::accept_inbound(target_node, target),
+        );
+
+        assert!(matches!(
+            outbound.unwrap_err(),
+            ConnectionError::Handshake(HandshakeError::Rejected(
+                HandshakeFailureReason::InvalidTarget
+            ))
+        ));
+        assert!(matches!(
+            inbound.unwrap_err(),
+            ConnectionError::Handshake(HandshakeError::Invalid(
+                HandshakeFailureReason::InvalidTarget
+            ))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tier1_handshake() -> Result<()> {
+        let validator_node = || {
+            Node::new_test(Network::Localnet.into())
+                .with_validator_key(SecretKey::from_random(KeyType::ED25519))
+        };
+        let (sender_node, target_node) = (Arc::new(validator_node()), Arc::new(validator_node()));
+
+        let (outbound, inbound) =
+            handshake_pair(sender_node.clone(), target_node.clone(), Tier::Tier1).await;
+        let (outbound, inbound) = (outbound?, inbound?);
+        assert_eq!(outbound.session().peer_id, target_node.peer_id());
+        assert_eq!(inbound.session().peer_id, sender_node.peer_id());
+
+        let sender_node = Arc::new(Node::new_test(Network::Localnet.into()));
+        let (_, inbound) = handshake_pair(sender_node, target_node, Tier::Tier1).await;
+        assert!(matches!(
+            inbound,
+            Err(ConnectionError::Handshake(
+                HandshakeError::MissingOwnedAccount
+            ))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handshake_timeout() -> Result<()> {
+        let (_sender, target) = tokio::io::duplex(4096);
+
+        let error = PeerConnection::accept_inbound(
+            Arc::new(Node::new_test(Network::Localnet.into())),
+            target,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, ConnectionError::Wire(Error::Timeout(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_frame_too_large() -> Result<()> {
+        let (outbound, inbound) = handshake_pair(
+            Arc::new(Node::new_test(Network::Localnet.into()).with_max_frame_size(1024)),
+            Arc::new(Node::new_test(Network::Localnet.into())),
+            Tier::Tier2,
+        )
+        .await;
+        let (mut outbound, mut inbound) = (outbound?, inbound?);
+
+        let too_large = PeerMessage::BlockHeadersRequest(vec![CryptoHash::default(); 32]);
+        assert!(matches!(
+            outbound.send(too_large).await.unwrap_err(),
+            Error::FrameTooLarge {
+                max_frame_size: 1024,
+                ..
+            }
+        ));
+
+        let block_request = PeerMessage::BlockRequest(CryptoHash::default());
+        outbound.send(block_request.clone()).await?;
+        assert_eq!(inbound.receive().await?, block_request);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spawn() -> Result<()> {
+        let (outbound, inbound) = connected_pair().await?;
+        let outbound_queue = OutboundQueue {
+            capacity: NonZeroUsize::new(8).unwrap(),
+            overflow_policy: OverflowPolicy::Drop,
+        };
+        let mut outbound = outbound.spawn(outbound_queue);
+        let mut inbound = inbound.spawn(outbound_queue);
+
+        // Both sides send before reading.
+        let (request, response) = (
+            PeerMessage::BlockRequest(CryptoHash::hash_bytes(b"request")),
+            PeerMessage::BlockRequest(CryptoHash::hash_bytes(b"response")),
+        );
+        let trace_context = TraceContext {
+            trace_id: TraceId::from_bytes(rand::random()),
+            span_id: SpanId::from_bytes(rand::random()),
+            sampling_priority: Some(SamplingPriority::AutoKeep),
+        };
+        {
+            let _guard = trace_context.context().attach();
+            outbound.send(request.clone())?;
+        }
+        inbound.send(response.clone())?;
+        assert_eq!(
+            inbound.receive_envelope().await?,
+            Envelope {
+                message: request,
+                trace_context: Some(trace_context),
+            }
+        );
+        assert_eq!(outbound.receive().await?, response);
+
+        outbound.close(Disconnect::default()).await?;
+        assert_eq!(
+            inbound.receive().await?,
+            PeerMessage::Disconnect(Disconnect::default())
+        );
+        assert_eq!(inbound.state(), ConnectionState::Closing);
+        assert!(matches!(
+            inbound.receive().await.unwrap_err(),
+            Error::ConnectionClosed
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drop() -> Result<()> {
+        let (outbound, mut inbound) = connected_pair().await?;
+        let outbound = outbound.spawn(OutboundQueue {
+            capacity: NonZeroUsize::new(8).unwrap(),
+            overflow_policy: OverflowPolicy::Drop,
+        });
+
+        let (reader, writer) = (
+            outbound.reader.abort_handle(),
+            outbound.writer.abort_handle(),
+        );
+
+        // The peer stays silent, so only an abort stops the reader task.
+        drop(outbound);
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while !reader.is_finished() || !writer.is_finished() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await?;
+        assert!(inbound.receive().await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_close_timeout() -> Result<()> {
+        let (outbound, _inbound) = connected_pair().await?;
+        let mut outbound = outbound.spawn(OutboundQueue {
+            capacity: NonZeroUsize::new(8).unwrap(),
+            overflow_policy: OverflowPolicy::Drop,
+        });
+
+        // Larger than the stream buffer, the writer task blocks as the peer never reads.
+        outbound.send(PeerMessage::BlockHeadersRequest(vec![
+            CryptoHash::default();
+            256
+        ]))?;
+        let error = outbound.close(Disconnect::default()).await.unwrap_err();
+        assert!(matches!(error, Error::Timeout(_)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_queue_full() -> Result<()> {
+        for overflow_policy in [OverflowPolicy::Drop, OverflowPolicy::Disconnect] {
+            let (outbound, mut inbound) = connected_pair().await?;
+            let mut outbound = outbound.spawn(OutboundQueue {
+                capacity: NonZeroUsize::new(1).unwrap(),
+                overflow_policy,
+            });
+
+            // Larger than the stream buffer, the writer task blocks on it until the peer reads.
+            let headers_request =
+                PeerMessage::BlockHeadersRequest(vec![CryptoHash::default(); 256]);
+            outbound.send(headers_request.clone())?;
+            tokio::time::timeout(Duration::from_secs(1), async {
+                while outbound.outbound.capacity() == 0 {
+                    tokio::task::yield_now().await;
+                }
+            })
+            .await?;
+
+            // The writer task holds the first message, the second one fills the queue.
+            outbound.send(headers_request.clone())?;
+            assert!(matches!(
+                outbound.send(headers_request.clone()).unwrap_err(),
+                Error::QueueFull
+            ));
+
+            match overflow_policy {
+                OverflowPolicy::Drop => {
+                    assert_eq!(outbound.state(), ConnectionState::Established);
+                    assert_eq!(inbound.receive().await?, headers_request);
+                    assert_eq!(inbound.receive().await?, headers_request);
+                }
+                OverflowPolicy::Disconnect => {
+                    assert_eq!(outbound.state(), ConnectionState::Closing);
+                    assert!(matches!(
+                        outbound.send(headers_request.clone()).unwrap_err(),
+                        Error::ConnectionClosed
+                    ));
+                    assert!(inbound.receive().await.is_err());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use near_primitives::network::PeerId;
+
+use crate::types::handshake_failure::HandshakeFailureReason;
+
+/// Errors of the wire layer: framing, protobuf and borsh decoding of `PeerMessage`s.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("protobuf error: {0}")]
+    Protobuf(#[from] protobuf::Error),
+    #[error("borsh decode error: {0}")]
+    Borsh(#[source] std::io::Error),
+    #[error("missing field `{0}`")]
+    MissingField(&'static str),
+    #[error("invalid field `{0}`")]
+    InvalidField(&'static str),
+    #[error("frame of {frame_size} bytes exceeds the limit of {max_frame_size} bytes")]
+    FrameTooLarge {
+        frame_size: usize,
+        max_frame_size: usize,
+    },
+    #[error("routed message TTL expired")]
+    TtlExpired,
+    #[error("outbound queue is full")]
+    QueueFull,
+    #[error("connection closed")]
+    ConnectionClosed,
+    #[error("timed out")]
+    Timeout(#[from] tokio::time::error::Elapsed),
+}
+
+/// Reasons a handshake isn't concluded.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum HandshakeError {
+    #[error("expected handshake, received {0}")]
+    UnexpectedMessage(&'static str),
+    #[error("handshake rejected by peer: {0:?}")]
+    Rejected(HandshakeFailureReason),
+    #[error("handshake is invalid: {0:?}")]
+    Invalid(HandshakeFailureReason),
+    #[error("invalid partial edge signature")]
+    InvalidSignature,
+    #[error("nonce {0} is not a valid timestamp nonce")]
+    InvalidNonce(u64),
+    #[error("nonce {0} is too far from the current time")]
+    NonceExpired(u64),
+    #[error("nonce {nonce} is not newer than the last accepted nonce {last_nonce}")]
+    NonceReused { nonce: u64, last_nonce: u64 },
+    #[error("expected nonce {expected}, received {received}")]
+    NonceMismatch { expected: u64, received: u64 },
+    #[error("expected peer {expected}, received {received}")]
+    PeerIdMismatch { expected: PeerId, received: PeerId },
+    #[error("TIER1 handshake without owned account")]
+    MissingOwnedAccount,
+    #[error("owned account timestamp is too far from the current time")]
+    OwnedAccountExpired,
+}
+
+/// Reasons a `PeerConnection` isn't established.
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectionError {
+    #[error("address of peer {0} is unknown")]
+    MissingAddress(PeerId),
+    #[error(transparent)]
+    Wire(#[from] Error),
+    #[error(transparent)]
+    Handshake(#[from] HandshakeError),
+}
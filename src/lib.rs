@@ -1,48 +1,140 @@
 #![allow(async_fn_in_trait)]
-use std::error::Error;
-use std::pin::Pin;
-
-use bytes::BytesMut;
-use protobuf::Message;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
+use bytes::{BufMut, BytesMut};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util::codec::{Decoder, Encoder};
 
+use crate::codec::{EnvelopeCodec, PeerMessageCodec};
+pub use crate::error::{ConnectionError, Error, HandshakeError};
+use crate::types::envelope::Envelope;
 use crate::types::peer_message::PeerMessage;
 
+pub mod codec;
 pub mod config;
+pub mod connection;
+mod error;
+#[allow(renamed_and_removed_lints)]
 mod proto;
 pub mod types;
 
-pub trait SendPeerMessage: AsyncWriteExt {
-    async fn send_peer_message(
-        mut self: Pin<&mut Self>,
-        peer_message: PeerMessage,
-    ) -> Result<(), Box<dyn Error>> {
-        let network_peer_message: proto::network::PeerMessage = peer_message.into();
+/// Writes `PeerMessage`s to any `AsyncWrite`, e.g. `TcpStream`, its `OwnedWriteHalf`
+/// or `tokio::io::duplex`.
+pub trait SendPeerMessage: AsyncWrite + Unpin {
+    /// Sends the message in an `Envelope` with the trace context of the current span.
+    async fn send_peer_message(&mut self, peer_message: PeerMessage) -> Result<(), Error> {
+        self.send_peer_message_with(Envelope::from(peer_message), &mut EnvelopeCodec::default())
+            .await
+    }
 
-        let message = network_peer_message.write_to_bytes()?;
-        let message_size = message.len();
+    /// Sends a single frame encoded by `codec`, e.g. `PeerMessageCodec` or `EnvelopeCodec`.
+    async fn send_peer_message_with<T, C: Encoder<T, Error = Error>>(
+        &mut self,
+        item: T,
+        codec: &mut C,
+    ) -> Result<(), Error> {
+        let mut buf = BytesMut::new();
+        codec.encode(item, &mut buf)?;
 
-        self.write_u32_le(message_size as u32).await?;
-        self.write_all(&message).await?;
+        self.write_all(&buf).await?;
         self.flush().await?;
 
         Ok(())
     }
 }
 
-pub trait ReceivePeerMessage: AsyncReadExt {
-    async fn receive_peer_message(mut self: Pin<&mut Self>) -> Result<PeerMessage, Box<dyn Error>> {
-        let message_size = self.read_u32_le().await? as usize;
+/// Reads `PeerMessage`s from any `AsyncRead`, e.g. `TcpStream`, its `OwnedReadHalf`
+/// or `tokio::io::duplex`.
+pub trait ReceivePeerMessage: AsyncRead + Unpin {
+    async fn receive_peer_message(&mut self) -> Result<PeerMessage, Error> {
+        self.receive_peer_message_with(&mut PeerMessageCodec::default())
+            .await
+    }
+
+    /// Receives the message along with the trace context of the sender.
+    async fn receive_envelope(&mut self) -> Result<Envelope, Error> {
+        self.receive_peer_message_with(&mut EnvelopeCodec::default())
+            .await
+    }
+
+    /// Receives a single frame decoded by `codec`, e.g. `PeerMessageCodec` or `EnvelopeCodec`.
+    async fn receive_peer_message_with<C: Decoder<Error = Error>>(
+        &mut self,
+        codec: &mut C,
+    ) -> Result<C::Item, Error> {
+        let message_size = self.read_u32_le().await?;
+
+        // Read exactly one frame, bytes after it belong to the next call.
+        let mut buf = BytesMut::new();
+        buf.put_u32_le(message_size);
+        let header_size = buf.len();
+
+        // With only the length prefix buffered the codec rejects oversized frames
+        // before the body is allocated or read.
+        if let Some(item) = codec.decode(&mut buf)? {
+            return Ok(item);
+        }
 
-        let mut buf = BytesMut::with_capacity(message_size);
-        self.read_buf(&mut buf).await?;
+        buf.resize(header_size + message_size as usize, 0);
+        self.read_exact(&mut buf[header_size..]).await?;
 
-        let message = proto::network::PeerMessage::parse_from_bytes(&buf)?;
-        message.try_into()
+        codec
+            .decode(&mut buf)?
+            .ok_or(Error::InvalidField("length prefix"))
     }
 }
 
-impl SendPeerMessage for TcpStream {}
+impl<T: AsyncWrite + Unpin + ?Sized> SendPeerMessage for T {}
+
+impl<T: AsyncRead + Unpin + ?Sized> ReceivePeerMessage for T {}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
 
-impl ReceivePeerMessage for TcpStream {}
+    use crate::config::Network;
+    use crate::types::handshake::Tier;
+    use crate::types::node::Node;
+    use crate::types::peer_message::PeerMessage;
+    use crate::{ReceivePeerMessage, SendPeerMessage};
+
+    #[tokio::test]
+    async fn test_handshake() -> Result<()> {
+        let (sender_node, target_node) = (
+            Node::new_test(Network::Localnet.into()),
+            Node::new_test(Network::Localnet.into()),
+        );
+        let (sender, mut target) = tokio::io::duplex(4096);
+        let (mut sender_reader, mut sender_writer) = tokio::io::split(sender);
+
+        let nonce = sender_node.create_nonce(&target_node.peer_id());
+        let sent = sender_node.create_handshake(target_node.peer_id(), nonce);
+        sender_writer
+            .send_peer_message(PeerMessage::Tier2Handshake(sent.clone()))
+            .await?;
+
+        let PeerMessage::Tier2Handshake(received) = target.receive_peer_message().await? else {
+            panic!("first message is Handshake");
+        };
+        assert!(target_node.verify_handshake_signature(&received));
+        let target_session = target_node.verify_handshake(&received).unwrap();
+        target_node.accept_nonce(&received.sender_peer_id, received.partial_edge_info.nonce)?;
+
+        let response = target_node.create_handshake(
+            received.sender_peer_id.clone(),
+            received.partial_edge_info.nonce,
+        );
+        target
+            .send_peer_message(PeerMessage::Tier2Handshake(response))
+            .await?;
+
+        let response = sender_reader.receive_peer_message().await?;
+        let sender_session =
+            sender_node.verify_handshake_response(Tier::Tier2, &sent, &response)?;
+
+        assert_eq!(sender_session.peer_id, target_node.peer_id());
+        assert_eq!(target_session.peer_id, sender_node.peer_id());
+        assert_eq!(sender_session.edge, target_session.edge);
+        assert!(sender_session.edge.verify());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,406 @@
+use near_crypto::{PublicKey, SecretKey, Signature};
+use near_network_primitives::time;
+use near_primitives::network::PeerId;
+use protobuf::Message;
+
+use crate::error::Error;
+use crate::proto;
+use crate::proto::network::account_key_payload::Payload_type;
+use crate::types::peer_addr::PeerAddr;
+use crate::types::peer_message::{utc_from_proto, utc_to_proto};
+
+/// Payload signed with an account key.
+/// The payload is kept serialized, as re-encoding protobuf isn't guaranteed
+/// to reproduce the signed bytes.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct AccountKeySignedPayload {
+    payload: Vec<u8>,
+    signature: Signature,
+}
+
+impl AccountKeySignedPayload {
+    fn sign(payload: Payload_type, secret_key: &SecretKey) -> Self {
+        let payload = proto::network::AccountKeyPayload {
+            payload_type: Some(payload),
+            ..Default::default()
+        }
+        .write_to_bytes()
+        .unwrap();
+        let signature = secret_key.sign(&payload);
+
+        Self { payload, signature }
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    pub fn verify(&self, account_key: &PublicKey) -> bool {
+        self.signature.verify(&self.payload, account_key)
+    }
+
+    fn decode(&self) -> Result<Payload_type, Error> {
+        proto::network::AccountKeyPayload::parse_from_bytes(&self.payload)?
+            .payload_type
+            .ok_or(Error::MissingField("payload_type"))
+    }
+}
+
+/// Announcement of the TIER1 proxies of a validator.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct AccountData {
+    /// Peer id of the node owning the account key.
+    pub peer_id: PeerId,
+    /// Validator key of the account.
+    pub account_key: PublicKey,
+    /// Trusted nodes connected to the validator which proxy traffic to it.
+    /// Empty if the validator has no public IP.
+    pub proxies: Vec<PeerAddr>,
+    /// Newer version overrides the previously announced data.
+    pub version: u64,
+    pub timestamp: time::Utc,
+}
+
+impl AccountData {
+    /// Signs the data with the validator key, which has to match `account_key`.
+    pub fn sign(self, secret_key: &SecretKey) -> SignedAccountData {
+        let payload = AccountKeySignedPayload::sign(
+            Payload_type::AccountData(self.clone().into()),
+            secret_key,
+        );
+
+        SignedAccountData {
+            account_data: self,
+            payload,
+        }
+    }
+}
+
+/// `AccountData` along with the signed payload it was decoded from.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SignedAccountData {
+    account_data: AccountData,
+    payload: AccountKeySignedPayload,
+}
+
+impl SignedAccountData {
+    pub fn account_data(&self) -> &AccountData {
+        &self.account_data
+    }
+
+    pub fn payload(&self) -> &AccountKeySignedPayload {
+        &self.payload
+    }
+}
+
+/// Proof that a node owns the validator key, included in handshakes of validator nodes.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct OwnedAccount {
+    /// Validator key of the account.
+    pub account_key: PublicKey,
+    /// Peer id of the node owning the account key.
+    pub peer_id: PeerId,
+    /// Time of signing. Handshakes with timestamps too far from the current time are rejected.
+    pub timestamp: time::Utc,
+}
+
+impl OwnedAccount {
+    /// Signs the proof with the validator key, which has to match `account_key`.
+    pub fn sign(self, secret_key: &SecretKey) -> SignedOwnedAccount {
+        let payload = AccountKeySignedPayload::sign(
+            Payload_type::OwnedAccount(self.clone().into()),
+            secret_key,
+        );
+
+        SignedOwnedAccount {
+            owned_account: self,
+            payload,
+        }
+    }
+}
+
+/// `OwnedAccount` along with the signed payload it was decoded from.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SignedOwnedAccount {
+    owned_account: OwnedAccount,
+    payload: AccountKeySignedPayload,
+}
+
+impl SignedOwnedAccount {
+    pub fn owned_account(&self) -> &OwnedAccount {
+        &self.owned_account
+    }
+
+    pub fn payload(&self) -> &AccountKeySignedPayload {
+        &self.payload
+    }
+}
+
+impl From<AccountKeySignedPayload> for proto::network::AccountKeySignedPayload {
+    fn from(value: AccountKeySignedPayload) -> Self {
+        Self {
+            payload: value.payload,
+            signature: Some(value.signature.into()).into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::AccountKeySignedPayload> for AccountKeySignedPayload {
+    type Error = Error;
+
+    fn try_from(value: proto::network::AccountKeySignedPayload) -> Result<Self, Self::Error> {
+        Ok(Self {
+            payload: value.payload,
+            signature: value
+                .signature
+                .into_option()
+                .ok_or(Error::MissingField("signature"))?
+                .try_into()?,
+        })
+    }
+}
+
+impl From<AccountData> for proto::network::AccountData {
+    fn from(value: AccountData) -> Self {
+        Self {
+            peer_id: Some(value.peer_id.into()).into(),
+            account_key: Some(value.account_key.into()).into(),
+            proxies: value.proxies.into_iter().map(Into::into).collect(),
+            version: value.version,
+            timestamp: Some(utc_to_proto(&value.timestamp)).into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::AccountData> for AccountData {
+    type Error = Error;
+
+    fn try_from(value: proto::network::AccountData) -> Result<Self, Self::Error> {
+        Ok(Self {
+            peer_id: value
+                .peer_id
+                .into_option()
+                .ok_or(Error::MissingField("peer_id"))?
+                .try_into()?,
+            account_key: value
+                .account_key
+                .into_option()
+                .ok_or(Error::MissingField("account_key"))?
+                .try_into()?,
+            proxies: value
+                .proxies
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            version: value.version,
+            timestamp: utc_from_proto(
+                value
+                    .timestamp
+                    .as_ref()
+                    .ok_or(Error::MissingField("timestamp"))?,
+            )
+            .map_err(|_| Error::InvalidField("timestamp"))?,
+        })
+    }
+}
+
+impl From<SignedAccountData> for proto::network::AccountKeySignedPayload {
+    fn from(value: SignedAccountData) -> Self {
+        value.payload.into()
+    }
+}
+
+impl TryFrom<proto::network::AccountKeySignedPayload> for SignedAccountData {
+    type Error = Error;
+
+    /// Fails if the payload isn't `AccountData` signed with its `account_key`.
+    fn try_from(value: proto::network::AccountKeySignedPayload) -> Result<Self, Self::Error> {
+        let payload = AccountKeySignedPayload::try_from(value)?;
+        let account_data: AccountData = match payload.decode()? {
+            Payload_type::AccountData(account_data) => account_data.try_into()?,
+            _ => return Err(Error::InvalidField("payload_type")),
+        };
+
+        if !payload.verify(&account_data.account_key) {
+            return Err(Error::InvalidField("signature"));
+        }
+
+        Ok(Self {
+            account_data,
+            payload,
+        })
+    }
+}
+
+impl From<OwnedAccount> for proto::network::OwnedAccount {
+    fn from(value: OwnedAccount) -> Self {
+        Self {
+            account_key: Some(value.account_key.into()).into(),
+            peer_id: Some(value.peer_id.into()).into(),
+            timestamp: Some(utc_to_proto(&value.timestamp)).into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::OwnedAccount> for OwnedAccount {
+    type Error = Error;
+
+    fn try_from(value: proto::network::OwnedAccount) -> Result<Self, Self::Error> {
+        Ok(Self {
+            account_key: value
+                .account_key
+                .into_option()
+                .ok_or(Error::MissingField("account_key"))?
+                .try_into()?,
+            peer_id: value
+                .peer_id
+                .into_option()
+                .ok_or(Error::MissingField("peer_id"))?
+                .try_into()?,
+            timestamp: utc_from_proto(
+                value
+                    .timestamp
+                    .as_ref()
+                    .ok_or(Error::MissingField("timestamp"))?,
+            )
+            .map_err(|_| Error::InvalidField("timestamp"))?,
+        })
+    }
+}
+
+impl From<SignedOwnedAccount> for proto::network::AccountKeySignedPayload {
+    fn from(value: SignedOwnedAccount) -> Self {
+        value.payload.into()
+    }
+}
+
+impl TryFrom<proto::network::AccountKeySignedPayload> for SignedOwnedAccount {
+    type Error = Error;
+
+    /// Fails if the payload isn't `OwnedAccount` signed with its `account_key`.
+    fn try_from(value: proto::network::AccountKeySignedPayload) -> Result<Self, Self::Error> {
+        let payload = AccountKeySignedPayload::try_from(value)?;
+        let owned_account: OwnedAccount = match payload.decode()? {
+            Payload_type::OwnedAccount(owned_account) => owned_account.try_into()?,
+            _ => return Err(Error::InvalidField("payload_type")),
+        };
+
+        if !payload.verify(&owned_account.account_key) {
+            return Err(Error::InvalidField("signature"));
+        }
+
+        Ok(Self {
+            owned_account,
+            payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use anyhow::Result;
+    use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, SecretKey};
+    use near_network_primitives::time;
+    use near_primitives::network::PeerId;
+    use rand::rngs::OsRng;
+
+    use crate::error::Error;
+    use crate::proto::network;
+    use crate::types::account_key::{
+        AccountData, OwnedAccount, SignedAccountData, SignedOwnedAccount,
+    };
+    use crate::types::peer_addr::PeerAddr;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let peer_id = |keypair: &ed25519_dalek::Keypair| {
+            PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+                keypair.public.to_bytes(),
+            )))
+        };
+        let account_keypair = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let account_secret_key = SecretKey::ED25519(ED25519SecretKey(account_keypair.to_bytes()));
+        let node_keypair = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let proxy_keypair = ed25519_dalek::Keypair::generate(&mut OsRng);
+
+        let account_data = AccountData {
+            peer_id: peer_id(&node_keypair),
+            account_key: account_secret_key.public_key(),
+            proxies: vec![PeerAddr {
+                addr: SocketAddr::from((Ipv4Addr::LOCALHOST, 24567)),
+                peer_id: peer_id(&proxy_keypair),
+            }],
+            version: 1,
+            timestamp: time::Utc::now_utc(),
+        };
+
+        let signed_account_data = account_data.clone().sign(&account_secret_key);
+        assert!(signed_account_data
+            .payload()
+            .verify(&account_data.account_key));
+
+        let network_payload: network::AccountKeySignedPayload = signed_account_data.clone().into();
+        let signed_account_data_restored: SignedAccountData = network_payload.try_into()?;
+        assert_eq!(signed_account_data, signed_account_data_restored);
+        assert_eq!(signed_account_data_restored.account_data(), &account_data);
+
+        // Signed with a key other than `account_key`.
+        let forged = account_data.sign(&SecretKey::ED25519(ED25519SecretKey(
+            proxy_keypair.to_bytes(),
+        )));
+        let network_payload: network::AccountKeySignedPayload = forged.into();
+        let error = SignedAccountData::try_from(network_payload).unwrap_err();
+        assert!(matches!(error, Error::InvalidField("signature")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_owned_account_serde() -> Result<()> {
+        let account_secret_key = SecretKey::ED25519(ED25519SecretKey(
+            ed25519_dalek::Keypair::generate(&mut OsRng).to_bytes(),
+        ));
+        let node_keypair = ed25519_dalek::Keypair::generate(&mut OsRng);
+
+        let owned_account = OwnedAccount {
+            account_key: account_secret_key.public_key(),
+            peer_id: PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+                node_keypair.public.to_bytes(),
+            ))),
+            timestamp: time::Utc::now_utc(),
+        };
+
+        let signed_owned_account = owned_account.clone().sign(&account_secret_key);
+        let network_payload: network::AccountKeySignedPayload = signed_owned_account.clone().into();
+        let signed_owned_account_restored: SignedOwnedAccount =
+            network_payload.clone().try_into()?;
+        assert_eq!(signed_owned_account, signed_owned_account_restored);
+        assert_eq!(
+            signed_owned_account_restored.owned_account(),
+            &owned_account
+        );
+
+        // Payload of a different type.
+        let error = SignedAccountData::try_from(network_payload).unwrap_err();
+        assert!(matches!(error, Error::InvalidField("payload_type")));
+
+        // Signed with the node key instead of the validator key.
+        let forged = owned_account.sign(&SecretKey::ED25519(ED25519SecretKey(
+            node_keypair.to_bytes(),
+        )));
+        let network_payload: network::AccountKeySignedPayload = forged.into();
+        let error = SignedOwnedAccount::try_from(network_payload).unwrap_err();
+        assert!(matches!(error, Error::InvalidField("signature")));
+
+        Ok(())
+    }
+}
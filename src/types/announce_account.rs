@@ -0,0 +1,58 @@
+use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::network::AnnounceAccount;
+
+use crate::error::Error;
+use crate::proto;
+
+impl From<AnnounceAccount> for proto::network::AnnounceAccount {
+    fn from(value: AnnounceAccount) -> Self {
+        Self {
+            borsh: value.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::AnnounceAccount> for AnnounceAccount {
+    type Error = Error;
+
+    fn try_from(value: proto::network::AnnounceAccount) -> Result<Self, Self::Error> {
+        Self::try_from_slice(&value.borsh).map_err(Error::Borsh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, SecretKey};
+    use near_primitives::network::{AnnounceAccount, PeerId};
+    use near_primitives::types::EpochId;
+    use rand::rngs::OsRng;
+
+    use crate::proto::network;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let keypair = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let peer_id = PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+            keypair.public.to_bytes(),
+        )));
+        let account_id = "test.near".parse()?;
+        let epoch_id = EpochId::default();
+        let signature = SecretKey::ED25519(ED25519SecretKey(keypair.to_bytes()))
+            .sign(AnnounceAccount::build_header_hash(&account_id, &peer_id, &epoch_id).as_ref());
+        let announce_account = AnnounceAccount {
+            account_id,
+            peer_id,
+            epoch_id,
+            signature,
+        };
+
+        let announce_account_original = announce_account.clone();
+        let network_announce_account: network::AnnounceAccount = announce_account.into();
+        let announce_account_restored: AnnounceAccount = network_announce_account.try_into()?;
+        assert_eq!(announce_account_original, announce_account_restored);
+
+        Ok(())
+    }
+}
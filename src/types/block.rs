@@ -0,0 +1,170 @@
+use near_primitives::block::Block;
+use near_primitives::block_header::BlockHeader;
+use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::hash::CryptoHash;
+
+use crate::error::Error;
+use crate::proto;
+
+impl From<Block> for proto::network::Block {
+    fn from(value: Block) -> Self {
+        Self {
+            borsh: value.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::Block> for Block {
+    type Error = Error;
+
+    fn try_from(value: proto::network::Block) -> Result<Self, Self::Error> {
+        Self::try_from_slice(&value.borsh).map_err(Error::Borsh)
+    }
+}
+
+impl From<BlockHeader> for proto::network::BlockHeader {
+    fn from(value: BlockHeader) -> Self {
+        Self {
+            borsh: value.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::BlockHeader> for BlockHeader {
+    type Error = Error;
+
+    fn try_from(value: proto::network::BlockHeader) -> Result<Self, Self::Error> {
+        Self::try_from_slice(&value.borsh).map_err(Error::Borsh)
+    }
+}
+
+impl From<Vec<CryptoHash>> for proto::network::BlockHeadersRequest {
+    fn from(value: Vec<CryptoHash>) -> Self {
+        Self {
+            block_hashes: value.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::BlockHeadersRequest> for Vec<CryptoHash> {
+    type Error = Error;
+
+    fn try_from(value: proto::network::BlockHeadersRequest) -> Result<Self, Self::Error> {
+        value
+            .block_hashes
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+}
+
+impl From<Vec<BlockHeader>> for proto::network::BlockHeadersResponse {
+    fn from(value: Vec<BlockHeader>) -> Self {
+        Self {
+            block_headers: value.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::BlockHeadersResponse> for Vec<BlockHeader> {
+    type Error = Error;
+
+    fn try_from(value: proto::network::BlockHeadersResponse) -> Result<Self, Self::Error> {
+        value
+            .block_headers
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+}
+
+impl From<CryptoHash> for proto::network::BlockRequest {
+    fn from(value: CryptoHash) -> Self {
+        Self {
+            block_hash: Some(value.into()).into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::BlockRequest> for CryptoHash {
+    type Error = Error;
+
+    fn try_from(value: proto::network::BlockRequest) -> Result<Self, Self::Error> {
+        value
+            .block_hash
+            .into_option()
+            .ok_or(Error::MissingField("block_hash"))?
+            .try_into()
+    }
+}
+
+impl From<Block> for proto::network::BlockResponse {
+    fn from(value: Block) -> Self {
+        Self {
+            block: Some(value.into()).into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::BlockResponse> for Block {
+    type Error = Error;
+
+    fn try_from(value: proto::network::BlockResponse) -> Result<Self, Self::Error> {
+        value
+            .block
+            .into_option()
+            .ok_or(Error::MissingField("block"))?
+            .try_into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_primitives::block::Block;
+    use near_primitives::block_header::BlockHeader;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::time::Clock;
+
+    use crate::proto::network;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let block = Block::genesis(
+            63,
+            vec![],
+            Clock::utc(),
+            0,
+            100,
+            1000,
+            CryptoHash::default(),
+        );
+        let header = block.header().clone();
+
+        let network_block_headers_request: network::BlockHeadersRequest =
+            vec![*header.hash(), *header.prev_hash()].into();
+        let block_hashes: Vec<CryptoHash> = network_block_headers_request.try_into()?;
+        assert_eq!(block_hashes, vec![*header.hash(), *header.prev_hash()]);
+
+        let network_block_headers_response: network::BlockHeadersResponse =
+            vec![header.clone()].into();
+        let block_headers: Vec<BlockHeader> = network_block_headers_response.try_into()?;
+        assert_eq!(block_headers, vec![header.clone()]);
+
+        let network_block_request: network::BlockRequest = (*header.hash()).into();
+        let block_hash: CryptoHash = network_block_request.try_into()?;
+        assert_eq!(&block_hash, header.hash());
+
+        let network_block_response: network::BlockResponse = block.clone().into();
+        let block_restored: Block = network_block_response.try_into()?;
+        assert_eq!(block, block_restored);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::challenge::Challenge;
+
+use crate::error::Error;
+use crate::proto;
+
+impl From<Challenge> for proto::network::Challenge {
+    fn from(value: Challenge) -> Self {
+        Self {
+            borsh: value.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::Challenge> for Challenge {
+    type Error = Error;
+
+    fn try_from(value: proto::network::Challenge) -> Result<Self, Self::Error> {
+        Self::try_from_slice(&value.borsh).map_err(Error::Borsh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_crypto::KeyType;
+    use near_primitives::challenge::{BlockDoubleSign, Challenge, ChallengeBody};
+    use near_primitives::validator_signer::InMemoryValidatorSigner;
+
+    use crate::proto::network;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let signer = InMemoryValidatorSigner::from_random("test.near".parse()?, KeyType::ED25519);
+        let challenge = Challenge::produce(
+            ChallengeBody::BlockDoubleSign(BlockDoubleSign {
+                left_block_header: vec![1, 2, 3],
+                right_block_header: vec![4, 5, 6],
+            }),
+            &signer,
+        );
+
+        let challenge_original = challenge.clone();
+        let network_challenge: network::Challenge = challenge.into();
+        let challenge_restored: Challenge = network_challenge.try_into()?;
+        assert_eq!(challenge_original, challenge_restored);
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use near_primitives::hash::CryptoHash;
 
+use crate::error::Error;
 use crate::proto;
 
 impl From<CryptoHash> for proto::network::CryptoHash {
@@ -12,10 +13,10 @@ impl From<CryptoHash> for proto::network::CryptoHash {
 }
 
 impl TryFrom<proto::network::CryptoHash> for CryptoHash {
-    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Error = Error;
 
     fn try_from(value: proto::network::CryptoHash) -> Result<Self, Self::Error> {
-        Self::try_from(value.hash.as_slice())
+        Self::try_from(value.hash.as_slice()).map_err(|_| Error::InvalidField("hash"))
     }
 }
 
@@ -34,7 +35,7 @@ mod tests {
         let crypto_hash =
             CryptoHash::try_from_slice(ed25519_dalek::SecretKey::generate(&mut OsRng).as_bytes())?;
 
-        let crypto_hash_original = crypto_hash.clone();
+        let crypto_hash_original = crypto_hash;
         let network_crypto_hash: network::CryptoHash = crypto_hash.into();
         let crypto_hash_restored: CryptoHash = network_crypto_hash.try_into().unwrap();
         assert_eq!(crypto_hash_original, crypto_hash_restored);
@@ -0,0 +1,44 @@
+use crate::proto;
+
+/// Sent by a node before closing the connection.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Disconnect {
+    /// Asks the peer to forget the connection instead of recording it as failed,
+    /// so that the peer doesn't try to reconnect.
+    pub remove_from_connection_store: bool,
+}
+
+impl From<Disconnect> for proto::network::Disconnect {
+    fn from(value: Disconnect) -> Self {
+        Self {
+            remove_from_connection_store: value.remove_from_connection_store,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<proto::network::Disconnect> for Disconnect {
+    fn from(value: proto::network::Disconnect) -> Self {
+        Self {
+            remove_from_connection_store: value.remove_from_connection_store,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::proto::network;
+    use crate::types::disconnect::Disconnect;
+
+    #[test]
+    fn test_serde() {
+        for remove_from_connection_store in [false, true] {
+            let disconnect = Disconnect {
+                remove_from_connection_store,
+            };
+            let network_disconnect: network::Disconnect = disconnect.into();
+            let disconnect_restored: Disconnect = network_disconnect.into();
+            assert_eq!(disconnect, disconnect_restored);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use near_network_primitives::types::Edge;
+use near_primitives::network::PeerId;
+
+use crate::error::Error;
+use crate::proto;
+
+/// Available route to `destination` of length `distance`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct AdvertisedPeerDistance {
+    pub destination: PeerId,
+    pub distance: u32,
+}
+
+/// Distances from `root` to other peers of the network, gossiped by the V2 routing protocol.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct DistanceVector {
+    /// Peer advertising the distances.
+    pub root: PeerId,
+    /// Distances the root has to other peers.
+    pub distances: Vec<AdvertisedPeerDistance>,
+    /// Spanning tree of signed edges achieving the advertised distances or better.
+    pub edges: Vec<Edge>,
+}
+
+impl From<AdvertisedPeerDistance> for proto::network::AdvertisedPeerDistance {
+    fn from(value: AdvertisedPeerDistance) -> Self {
+        Self {
+            destination: Some(value.destination.into()).into(),
+            distance: value.distance,
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::AdvertisedPeerDistance> for AdvertisedPeerDistance {
+    type Error = Error;
+
+    fn try_from(value: proto::network::AdvertisedPeerDistance) -> Result<Self, Self::Error> {
+        Ok(Self {
+            destination: value
+                .destination
+                .into_option()
+                .ok_or(Error::MissingField("destination"))?
+                .try_into()?,
+            distance: value.distance,
+        })
+    }
+}
+
+impl From<DistanceVector> for proto::network::DistanceVector {
+    fn from(value: DistanceVector) -> Self {
+        Self {
+            root: Some(value.root.into()).into(),
+            distances: value.distances.into_iter().map(Into::into).collect(),
+            edges: value.edges.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::DistanceVector> for DistanceVector {
+    type Error = Error;
+
+    /// Fails if any of the spanning tree edges isn't signed by both of its peers.
+    fn try_from(value: proto::network::DistanceVector) -> Result<Self, Self::Error> {
+        let edges = value
+            .edges
+            .into_iter()
+            .map(Edge::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        // Hashing an edge asserts its peers are ordered, so self-loops are rejected first.
+        if edges.iter().any(|edge| edge.key().0 >= edge.key().1) {
+            return Err(Error::InvalidField("edges"));
+        }
+        if !edges.iter().all(Edge::verify) {
+            return Err(Error::InvalidField("edges"));
+        }
+
+        Ok(Self {
+            root: value
+                .root
+                .into_option()
+                .ok_or(Error::MissingField("root"))?
+                .try_into()?,
+            distances: value
+                .distances
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            edges,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, SecretKey};
+    use near_network_primitives::types::{Edge, PartialEdgeInfo};
+    use near_primitives::network::PeerId;
+    use rand::rngs::OsRng;
+
+    use crate::error::Error;
+    use crate::proto::network;
+    use crate::types::distance_vector::{AdvertisedPeerDistance, DistanceVector};
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let keypair0 = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let keypair1 = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let peer0 = PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+            keypair0.public.to_bytes(),
+        )));
+        let peer1 = PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+            keypair1.public.to_bytes(),
+        )));
+        let partial_edge_info = PartialEdgeInfo::new(
+            &peer1,
+            &peer0,
+            1,
+            &SecretKey::ED25519(ED25519SecretKey(keypair1.to_bytes())),
+        );
+        let edge = Edge::build_with_secret_key(
+            peer0.clone(),
+            peer1.clone(),
+            1,
+            &SecretKey::ED25519(ED25519SecretKey(keypair0.to_bytes())),
+            partial_edge_info.signature,
+        );
+
+        let distance_vector = DistanceVector {
+            root: peer0.clone(),
+            distances: vec![
+                AdvertisedPeerDistance {
+                    destination: peer0.clone(),
+                    distance: 0,
+                },
+                AdvertisedPeerDistance {
+                    destination: peer1.clone(),
+                    distance: 1,
+                },
+            ],
+            edges: vec![edge],
+        };
+
+        let distance_vector_original = distance_vector.clone();
+        let network_distance_vector: network::DistanceVector = distance_vector.into();
+        let distance_vector_restored: DistanceVector = network_distance_vector.try_into()?;
+        assert_eq!(distance_vector_original, distance_vector_restored);
+
+        let network_distance_vector: network::DistanceVector = DistanceVector {
+            edges: vec![Edge::make_fake_edge(peer0.clone(), peer1, 1)],
+            ..distance_vector_original.clone()
+        }
+        .into();
+        let error = DistanceVector::try_from(network_distance_vector).unwrap_err();
+        assert!(matches!(error, Error::InvalidField("edges")));
+
+        let network_distance_vector: network::DistanceVector = DistanceVector {
+            edges: vec![Edge::make_fake_edge(peer0.clone(), peer0, 1)],
+            ..distance_vector_original
+        }
+        .into();
+        let error = DistanceVector::try_from(network_distance_vector).unwrap_err();
+        assert!(matches!(error, Error::InvalidField("edges")));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use near_network_primitives::types::Edge;
+use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::error::Error;
+use crate::proto;
+
+impl From<Edge> for proto::network::Edge {
+    fn from(value: Edge) -> Self {
+        Self {
+            borsh: value.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::Edge> for Edge {
+    type Error = Error;
+
+    fn try_from(value: proto::network::Edge) -> Result<Self, Self::Error> {
+        Self::try_from_slice(&value.borsh).map_err(Error::Borsh)
+    }
+}
+
+impl From<Edge> for proto::network::LastEdge {
+    fn from(value: Edge) -> Self {
+        Self {
+            edge: Some(value.into()).into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::LastEdge> for Edge {
+    type Error = Error;
+
+    fn try_from(value: proto::network::LastEdge) -> Result<Self, Self::Error> {
+        value
+            .edge
+            .into_option()
+            .ok_or(Error::MissingField("edge"))?
+            .try_into()
+    }
+}
+
+impl From<Edge> for proto::network::UpdateNonceResponse {
+    fn from(value: Edge) -> Self {
+        Self {
+            edge: Some(value.into()).into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::UpdateNonceResponse> for Edge {
+    type Error = Error;
+
+    fn try_from(value: proto::network::UpdateNonceResponse) -> Result<Self, Self::Error> {
+        value
+            .edge
+            .into_option()
+            .ok_or(Error::MissingField("edge"))?
+            .try_into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, SecretKey};
+    use near_network_primitives::types::{Edge, PartialEdgeInfo};
+    use near_primitives::network::PeerId;
+    use rand::rngs::OsRng;
+
+    use crate::proto::network;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let keypair0 = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let keypair1 = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let peer0 = PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+            keypair0.public.to_bytes(),
+        )));
+        let peer1 = PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+            keypair1.public.to_bytes(),
+        )));
+        let partial_edge_info = PartialEdgeInfo::new(
+            &peer1,
+            &peer0,
+            1,
+            &SecretKey::ED25519(ED25519SecretKey(keypair1.to_bytes())),
+        );
+        let edge = Edge::build_with_secret_key(
+            peer0,
+            peer1,
+            1,
+            &SecretKey::ED25519(ED25519SecretKey(keypair0.to_bytes())),
+            partial_edge_info.signature,
+        );
+        assert!(edge.verify());
+
+        let network_edge: network::Edge = edge.clone().into();
+        let edge_restored: Edge = network_edge.try_into()?;
+        assert_eq!(edge, edge_restored);
+
+        let network_last_edge: network::LastEdge = edge.clone().into();
+        let last_edge_restored: Edge = network_last_edge.try_into()?;
+        assert_eq!(edge, last_edge_restored);
+
+        let network_update_nonce_response: network::UpdateNonceResponse = edge.clone().into();
+        let update_nonce_response_restored: Edge = network_update_nonce_response.try_into()?;
+        assert_eq!(edge, update_nonce_response_restored);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use opentelemetry::Context;
+use protobuf::{Message, MessageField};
+
+use crate::error::Error;
+use crate::proto;
+use crate::types::peer_message::PeerMessage;
+use crate::types::trace_context::TraceContext;
+
+/// `PeerMessage` as sent over the wire, with the trace context of the sender.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Envelope {
+    pub message: PeerMessage,
+    pub trace_context: Option<TraceContext>,
+}
+
+impl Envelope {
+    /// Context to run the handling of the message in, so that its spans are
+    /// children of the sender's span.
+    pub fn context(&self) -> Context {
+        self.trace_context
+            .as_ref()
+            .map(TraceContext::context)
+            .unwrap_or_else(Context::current)
+    }
+
+    /// Decodes the protobuf encoded message, see `PeerMessage::parse_from_bytes`.
+    pub fn parse_from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        proto::network::PeerMessage::parse_from_bytes(bytes)?.try_into()
+    }
+
+    pub fn write_to_bytes(self) -> Result<Vec<u8>, Error> {
+        Ok(proto::network::PeerMessage::from(self).write_to_bytes()?)
+    }
+}
+
+/// Wraps the message with the context of the current span.
+impl From<PeerMessage> for Envelope {
+    fn from(value: PeerMessage) -> Self {
+        Self {
+            message: value,
+            trace_context: TraceContext::current(),
+        }
+    }
+}
+
+impl From<Envelope> for proto::network::PeerMessage {
+    fn from(value: Envelope) -> Self {
+        Self {
+            trace_context: MessageField::from_option(value.trace_context.map(Into::into)),
+            ..value.message.into()
+        }
+    }
+}
+
+impl TryFrom<proto::network::PeerMessage> for Envelope {
+    type Error = Error;
+
+    fn try_from(mut value: proto::network::PeerMessage) -> Result<Self, Self::Error> {
+        let trace_context = value
+            .trace_context
+            .take()
+            .map(TraceContext::try_from)
+            .transpose()?;
+
+        Ok(Self {
+            message: value.try_into()?,
+            trace_context,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use opentelemetry::trace::{SpanId, TraceContextExt, TraceId};
+    use protobuf::Message;
+
+    use crate::proto::network;
+    use crate::types::disconnect::Disconnect;
+    use crate::types::envelope::Envelope;
+    use crate::types::peer_message::PeerMessage;
+    use crate::types::trace_context::{SamplingPriority, TraceContext};
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let trace_context = TraceContext {
+            trace_id: TraceId::from_bytes(rand::random()),
+            span_id: SpanId::from_bytes(rand::random()),
+            sampling_priority: Some(SamplingPriority::AutoKeep),
+        };
+
+        for trace_context in [None, Some(trace_context)] {
+            let envelope = Envelope {
+                message: PeerMessage::Disconnect(Disconnect::default()),
+                trace_context,
+            };
+
+            let network_peer_message: network::PeerMessage = envelope.clone().into();
+            let envelope_restored: Envelope = network_peer_message.try_into()?;
+            assert_eq!(envelope, envelope_restored);
+
+            let bytes = envelope.clone().write_to_bytes()?;
+            assert_eq!(envelope, Envelope::parse_from_bytes(&bytes)?);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown() -> Result<()> {
+        let trace_context = TraceContext {
+            trace_id: TraceId::from_bytes(rand::random()),
+            span_id: SpanId::from_bytes(rand::random()),
+            sampling_priority: None,
+        };
+
+        let mut network_peer_message = network::PeerMessage {
+            trace_context: Some(trace_context.into()).into(),
+            ..Default::default()
+        };
+        network_peer_message
+            .special_fields
+            .mut_unknown_fields()
+            .add_length_delimited(99, vec![1, 2, 3]);
+        let bytes = network_peer_message.write_to_bytes()?;
+
+        let envelope = Envelope::parse_from_bytes(&bytes)?;
+        assert_eq!(envelope.trace_context, Some(trace_context));
+        assert!(matches!(
+            envelope.message,
+            PeerMessage::Unknown { kind: 99, .. }
+        ));
+        assert_eq!(
+            Envelope::parse_from_bytes(&envelope.write_to_bytes()?)?.trace_context,
+            Some(trace_context)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_context() {
+        let trace_context = TraceContext {
+            trace_id: TraceId::from_bytes(rand::random()),
+            span_id: SpanId::from_bytes(rand::random()),
+            sampling_priority: Some(SamplingPriority::AutoKeep),
+        };
+        let _guard = trace_context.context().attach();
+
+        let envelope = Envelope::from(PeerMessage::Disconnect(Disconnect::default()));
+        assert_eq!(envelope.trace_context, Some(trace_context));
+
+        let context = envelope.context();
+        let span_context = context.span().span_context().clone();
+        assert!(span_context.is_remote());
+        assert_eq!(span_context.trace_id(), trace_context.trace_id);
+    }
+}
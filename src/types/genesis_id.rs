@@ -1,8 +1,7 @@
 use near_primitives::block::GenesisId;
-use near_primitives::borsh::BorshDeserialize;
-use near_primitives::hash::CryptoHash;
 use protobuf::MessageField;
 
+use crate::error::Error;
 use crate::proto;
 
 impl From<GenesisId> for proto::network::GenesisId {
@@ -16,12 +15,16 @@ impl From<GenesisId> for proto::network::GenesisId {
 }
 
 impl TryFrom<proto::network::GenesisId> for GenesisId {
-    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Error = Error;
 
     fn try_from(value: proto::network::GenesisId) -> Result<Self, Self::Error> {
         Ok(Self {
             chain_id: value.chain_id,
-            hash: CryptoHash::try_from_slice(value.hash.hash.as_slice())?,
+            hash: value
+                .hash
+                .into_option()
+                .ok_or(Error::MissingField("hash"))?
+                .try_into()?,
         })
     }
 }
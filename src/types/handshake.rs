@@ -1,11 +1,19 @@
 use near_network_primitives::types::{PartialEdgeInfo, PeerChainInfoV2};
-use near_primitives::block::GenesisId;
-use near_primitives::borsh::BorshDeserialize;
-use near_primitives::hash::CryptoHash;
 use near_primitives::network::PeerId;
 use protobuf::MessageField;
 
+use crate::error::Error;
 use crate::proto;
+use crate::types::account_key::SignedOwnedAccount;
+
+/// Network tier of a connection, selecting the handshake message type.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Tier {
+    /// Connections between validators, which prove owning their account key.
+    Tier1,
+    /// Regular connections between peers.
+    Tier2,
+}
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Handshake {
@@ -23,6 +31,8 @@ pub struct Handshake {
     pub sender_chain_info: PeerChainInfoV2,
     /// Represents new `edge`. Contains only `nonce` and `Signature` from the sender.
     pub partial_edge_info: PartialEdgeInfo,
+    /// Proof of owning a validator key, sent by validator nodes.
+    pub owned_account: Option<SignedOwnedAccount>,
 }
 
 impl From<Handshake> for proto::network::Handshake {
@@ -35,42 +45,47 @@ impl From<Handshake> for proto::network::Handshake {
             sender_listen_port: value.sender_listen_port.map_or(0, u32::from),
             sender_chain_info: MessageField::some(value.sender_chain_info.into()),
             partial_edge_info: MessageField::some(value.partial_edge_info.into()),
-            owned_account: MessageField::none(),
+            owned_account: MessageField::from_option(value.owned_account.map(Into::into)),
             ..Default::default()
         }
     }
 }
 
 impl TryFrom<proto::network::Handshake> for Handshake {
-    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Error = Error;
 
     fn try_from(value: proto::network::Handshake) -> Result<Self, Self::Error> {
         Ok(Self {
             protocol_version: value.protocol_version,
             oldest_supported_version: value.oldest_supported_version,
-            sender_peer_id: PeerId::try_from_slice(value.sender_peer_id.borsh.as_slice())?,
-            target_peer_id: PeerId::try_from_slice(value.target_peer_id.borsh.as_slice())?,
-            sender_listen_port: u16::try_from(value.sender_listen_port).map(|port| {
-                if port == 0 {
-                    None
-                } else {
-                    Some(port)
-                }
-            })?,
-            sender_chain_info: PeerChainInfoV2 {
-                genesis_id: GenesisId {
-                    chain_id: value.sender_chain_info.genesis_id.chain_id.clone(),
-                    hash: CryptoHash::try_from_slice(
-                        value.sender_chain_info.genesis_id.hash.hash.as_slice(),
-                    )?,
-                },
-                height: value.sender_chain_info.height,
-                tracked_shards: value.sender_chain_info.tracked_shards.clone(),
-                archival: value.sender_chain_info.archival,
-            },
-            partial_edge_info: PartialEdgeInfo::try_from_slice(
-                value.partial_edge_info.borsh.as_slice(),
-            )?,
+            sender_peer_id: value
+                .sender_peer_id
+                .into_option()
+                .ok_or(Error::MissingField("sender_peer_id"))?
+                .try_into()?,
+            target_peer_id: value
+                .target_peer_id
+                .into_option()
+                .ok_or(Error::MissingField("target_peer_id"))?
+                .try_into()?,
+            sender_listen_port: u16::try_from(value.sender_listen_port)
+                .map(|port| if port == 0 { None } else { Some(port) })
+                .map_err(|_| Error::InvalidField("sender_listen_port"))?,
+            sender_chain_info: value
+                .sender_chain_info
+                .into_option()
+                .ok_or(Error::MissingField("sender_chain_info"))?
+                .try_into()?,
+            partial_edge_info: value
+                .partial_edge_info
+                .into_option()
+                .ok_or(Error::MissingField("partial_edge_info"))?
+                .try_into()?,
+            owned_account: value
+                .owned_account
+                .into_option()
+                .map(TryInto::try_into)
+                .transpose()?,
         })
     }
 }
@@ -79,6 +94,7 @@ impl TryFrom<proto::network::Handshake> for Handshake {
 mod tests {
     use anyhow::Result;
     use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, SecretKey};
+    use near_network_primitives::time;
     use near_network_primitives::types::{PartialEdgeInfo, PeerChainInfoV2};
     use near_primitives::block::GenesisId;
     use near_primitives::borsh::BorshDeserialize;
@@ -87,6 +103,7 @@ mod tests {
     use rand::rngs::OsRng;
 
     use crate::proto::network;
+    use crate::types::account_key::OwnedAccount;
     use crate::types::handshake::Handshake;
 
     #[test]
@@ -115,14 +132,25 @@ mod tests {
         let secret_key = SecretKey::ED25519(ED25519SecretKey(keypair.to_bytes()));
         let partial_edge_info =
             PartialEdgeInfo::new(&sender_peer_id, &target_peer_id, 1, &secret_key);
+        let validator_key = SecretKey::ED25519(ED25519SecretKey(
+            ed25519_dalek::Keypair::generate(&mut OsRng).to_bytes(),
+        ));
         let handshake = Handshake {
             protocol_version: 63,
             oldest_supported_version: 61,
-            sender_peer_id,
+            sender_peer_id: sender_peer_id.clone(),
             target_peer_id,
             sender_listen_port: Some(51200),
             sender_chain_info,
             partial_edge_info,
+            owned_account: Some(
+                OwnedAccount {
+                    account_key: validator_key.public_key(),
+                    peer_id: sender_peer_id.clone(),
+                    timestamp: time::Utc::now_utc(),
+                }
+                .sign(&validator_key),
+            ),
         };
 
         let handshake_original = handshake.clone();
@@ -0,0 +1,132 @@
+use near_network_primitives::types::PeerInfo;
+use near_primitives::block::GenesisId;
+use protobuf::MessageField;
+
+use crate::error::Error;
+use crate::proto;
+use crate::proto::network::handshake_failure::Reason;
+
+/// Reason sent back in `HandshakeFailure` when a peer rejects the handshake.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum HandshakeFailureReason {
+    /// Protocol version range supported by the rejecting peer.
+    ProtocolVersionMismatch {
+        version: u32,
+        oldest_supported_version: u32,
+    },
+    /// Genesis of the chain the rejecting peer belongs to.
+    GenesisMismatch(GenesisId),
+    /// Handshake `target_peer_id` doesn't match the rejecting peer.
+    InvalidTarget,
+}
+
+impl From<(PeerInfo, HandshakeFailureReason)> for proto::network::HandshakeFailure {
+    fn from((peer_info, reason): (PeerInfo, HandshakeFailureReason)) -> Self {
+        let peer_info = MessageField::some(peer_info.into());
+        match reason {
+            HandshakeFailureReason::ProtocolVersionMismatch {
+                version,
+                oldest_supported_version,
+            } => Self {
+                reason: Reason::ProtocolVersionMismatch.into(),
+                peer_info,
+                version,
+                oldest_supported_version,
+                ..Default::default()
+            },
+            HandshakeFailureReason::GenesisMismatch(genesis_id) => Self {
+                reason: Reason::GenesisMismatch.into(),
+                peer_info,
+                genesis_id: MessageField::some(genesis_id.into()),
+                ..Default::default()
+            },
+            HandshakeFailureReason::InvalidTarget => Self {
+                reason: Reason::InvalidTarget.into(),
+                peer_info,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl TryFrom<proto::network::HandshakeFailure> for (PeerInfo, HandshakeFailureReason) {
+    type Error = Error;
+
+    fn try_from(value: proto::network::HandshakeFailure) -> Result<Self, Self::Error> {
+        let peer_info = value
+            .peer_info
+            .into_option()
+            .ok_or(Error::MissingField("peer_info"))?
+            .try_into()?;
+
+        let reason = match value.reason.enum_value() {
+            Ok(Reason::ProtocolVersionMismatch) => {
+                HandshakeFailureReason::ProtocolVersionMismatch {
+                    version: value.version,
+                    oldest_supported_version: value.oldest_supported_version,
+                }
+            }
+            Ok(Reason::GenesisMismatch) => HandshakeFailureReason::GenesisMismatch(
+                value
+                    .genesis_id
+                    .into_option()
+                    .ok_or(Error::MissingField("genesis_id"))?
+                    .try_into()?,
+            ),
+            Ok(Reason::InvalidTarget) => HandshakeFailureReason::InvalidTarget,
+            Ok(Reason::UNKNOWN) | Err(_) => return Err(Error::InvalidField("reason")),
+        };
+
+        Ok((peer_info, reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_crypto::{ED25519PublicKey, PublicKey};
+    use near_network_primitives::types::PeerInfo;
+    use near_primitives::block::GenesisId;
+    use near_primitives::borsh::BorshDeserialize;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::network::PeerId;
+    use rand::rngs::OsRng;
+
+    use crate::proto::network;
+    use crate::types::handshake_failure::HandshakeFailureReason;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let peer_info = PeerInfo {
+            id: PeerId::new(PublicKey::ED25519(ED25519PublicKey::from(
+                ed25519_dalek::SecretKey::generate(&mut OsRng).to_bytes(),
+            ))),
+            addr: None,
+            account_id: None,
+        };
+        let genesis_id = GenesisId {
+            chain_id: "testnet".to_string(),
+            hash: CryptoHash::try_from_slice(
+                ed25519_dalek::SecretKey::generate(&mut OsRng).as_bytes(),
+            )?,
+        };
+
+        for reason in [
+            HandshakeFailureReason::ProtocolVersionMismatch {
+                version: 63,
+                oldest_supported_version: 61,
+            },
+            HandshakeFailureReason::GenesisMismatch(genesis_id),
+            HandshakeFailureReason::InvalidTarget,
+        ] {
+            let handshake_failure_original = (peer_info.clone(), reason);
+            let network_handshake_failure: network::HandshakeFailure =
+                handshake_failure_original.clone().into();
+            let handshake_failure_restored: (PeerInfo, HandshakeFailureReason) =
+                network_handshake_failure.try_into()?;
+            assert_eq!(handshake_failure_original, handshake_failure_restored);
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,29 @@
+pub mod account_key;
+pub mod announce_account;
+pub mod block;
+pub mod challenge;
 pub mod crypto_hash;
+pub mod disconnect;
+pub mod distance_vector;
+pub mod edge;
+pub mod envelope;
 pub mod genesis_id;
 pub mod handshake;
+pub mod handshake_failure;
 pub mod node;
 pub mod partial_edge_info;
+pub mod peer_addr;
 pub mod peer_chain_info;
 pub mod peer_id;
+pub mod peer_info;
 pub mod peer_message;
+pub mod peer_table;
+pub mod peers;
 pub mod public_key;
+pub mod routed_message;
+pub mod routing_table_update;
+pub mod session;
+pub mod signature;
+pub mod sync_accounts_data;
+pub mod trace_context;
+pub mod transaction;
@@ -1,15 +1,34 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
 use ed25519_dalek::Keypair;
-use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, SecretKey};
+use near_crypto::{ED25519PublicKey, ED25519SecretKey, KeyFile, PublicKey, SecretKey};
 use near_network_primitives::time;
 use near_network_primitives::types::{
-    AccountOrPeerIdOrHash, Edge, PartialEdgeInfo, PeerChainInfoV2, Ping, Pong, RawRoutedMessage,
-    RoutedMessageBody, RoutedMessageV2,
+    AccountOrPeerIdOrHash, Edge, PartialEdgeInfo, PeerChainInfoV2, PeerInfo, Ping, Pong,
+    RawRoutedMessage, RoutedMessageBody,
 };
 use near_primitives::network::PeerId;
 use rand::rngs::OsRng;
 
+use crate::codec::EnvelopeCodec;
 use crate::config::Config;
-use crate::types::handshake::Handshake;
+use crate::error::HandshakeError;
+use crate::types::account_key::{OwnedAccount, SignedOwnedAccount};
+use crate::types::handshake::{Handshake, Tier};
+use crate::types::handshake_failure::HandshakeFailureReason;
+use crate::types::peer_message::PeerMessage;
+use crate::types::peer_table::PeerTable;
+use crate::types::routed_message::RoutedMessage;
+use crate::types::session::Session;
+
+/// Maximum allowed difference between the edge nonce timestamp and the current time.
+pub const EDGE_NONCE_MAX_TIME_DELTA: time::Duration = time::Duration::minutes(20);
+
+/// Maximum allowed difference between the owned account timestamp and the current time.
+pub const OWNED_ACCOUNT_MAX_CLOCK_SKEW: time::Duration = time::Duration::minutes(30);
 
 #[derive(Debug)]
 pub struct Node {
@@ -18,16 +37,109 @@ pub struct Node {
     oldest_supported_version: u32,
     sender_listen_port: u16,
     peer_chain_info: PeerChainInfoV2,
+    /// Nonce of the last edge established with every peer.
+    edge_nonces: Mutex<HashMap<PeerId, u64>>,
+    /// Peers known to this node, shared with peers asking for them.
+    peer_table: Mutex<PeerTable>,
+    /// Key of the validator account run by this node, proven in handshakes.
+    validator_key: Option<SecretKey>,
+    /// TTL of routed messages created by this node.
+    routed_message_ttl: u8,
+    /// Maximum size of a message accepted from peers.
+    max_frame_size: usize,
 }
 
-impl From<Config> for Node {
-    fn from(value: Config) -> Self {
-        Self {
-            key_pair: Keypair::generate(&mut OsRng),
+impl TryFrom<Config> for Node {
+    type Error = io::Error;
+
+    fn try_from(value: Config) -> Result<Self, Self::Error> {
+        let key_pair = match &value.node_key {
+            Some(node_key) if node_key.exists() => key_pair_from_file(node_key)?,
+            _ => Keypair::generate(&mut OsRng),
+        };
+
+        let node = Self {
+            key_pair,
             protocol_version: value.protocol_version,
             oldest_supported_version: value.oldest_supported_version,
             sender_listen_port: value.sender_listen_port,
             peer_chain_info: value.network.into(),
+            edge_nonces: Default::default(),
+            peer_table: Default::default(),
+            validator_key: value
+                .validator_key
+                .as_deref()
+                .map(secret_key_from_file)
+                .transpose()?,
+            routed_message_ttl: value.routed_message_ttl,
+            max_frame_size: value.max_frame_size,
+        };
+
+        if let Some(node_key) = value.node_key.filter(|node_key| !node_key.exists()) {
+            node.key_file().write_to_file(&node_key)?;
+        }
+
+        Ok(node)
+    }
+}
+
+/// Reads the secret key from nearcore `node_key.json` or `validator_key.json`.
+fn secret_key_from_file(path: &Path) -> io::Result<SecretKey> {
+    let key_file = KeyFile::from_file(path)?;
+
+    if key_file.secret_key.public_key() != key_file.public_key {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "public key doesn't match secret key",
+        ));
+    }
+
+    Ok(key_file.secret_key)
+}
+
+/// Reads the key pair from nearcore `node_key.json`.
+fn key_pair_from_file(path: &Path) -> io::Result<Keypair> {
+    let SecretKey::ED25519(ED25519SecretKey(secret_key)) = secret_key_from_file(path)? else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "node key must be ed25519",
+        ));
+    };
+
+    Keypair::from_bytes(&secret_key).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+impl Node {
+    /// Node with a fresh key and default settings of `Config`.
+    pub(crate) fn new_test(peer_chain_info: PeerChainInfoV2) -> Self {
+        Self {
+            key_pair: Keypair::generate(&mut OsRng),
+            protocol_version: 63,
+            oldest_supported_version: 61,
+            sender_listen_port: 34567,
+            peer_chain_info,
+            edge_nonces: Default::default(),
+            peer_table: Default::default(),
+            validator_key: None,
+            routed_message_ttl: near_network_primitives::types::ROUTED_MESSAGE_TTL,
+            max_frame_size: crate::codec::NETWORK_MESSAGE_MAX_SIZE_BYTES,
+        }
+    }
+
+    /// Same node limiting the messages to `max_frame_size` bytes.
+    pub(crate) fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        Self {
+            max_frame_size,
+            ..self
+        }
+    }
+
+    /// Same node owning the `validator_key` account.
+    pub(crate) fn with_validator_key(self, validator_key: SecretKey) -> Self {
+        Self {
+            validator_key: Some(validator_key),
+            ..self
         }
     }
 }
@@ -48,6 +160,80 @@ impl Node {
     pub fn secret_key(&self) -> SecretKey {
         SecretKey::ED25519(ED25519SecretKey(self.as_ref().to_bytes()))
     }
+
+    /// Codec for the connections of this node.
+    pub fn codec(&self) -> EnvelopeCodec {
+        EnvelopeCodec::new(self.max_frame_size)
+    }
+
+    /// Node identity in nearcore `node_key.json` format.
+    pub fn key_file(&self) -> KeyFile {
+        KeyFile {
+            account_id: "node".parse().unwrap(),
+            public_key: self.peer_id().public_key().clone(),
+            secret_key: self.secret_key(),
+        }
+    }
+
+    /// Creates a nonce for a new edge with the peer from the current unix timestamp,
+    /// newer than the nonce of the last edge with the peer. Nonces of active edges are odd.
+    pub fn create_nonce(&self, peer_id: &PeerId) -> u64 {
+        let nonce = time::Utc::now_utc().unix_timestamp() as u64 | 1;
+
+        match self.edge_nonces.lock().unwrap().get(peer_id) {
+            Some(&last_nonce) => nonce.max(last_nonce + 2),
+            None => nonce,
+        }
+    }
+
+    /// Remembers the nonce of our own edge proposal once the peer accepted it.
+    fn record_nonce(&self, peer_id: &PeerId, nonce: u64) {
+        let mut edge_nonces = self.edge_nonces.lock().unwrap();
+        let last_nonce = edge_nonces.entry(peer_id.clone()).or_insert(nonce);
+        *last_nonce = nonce.max(*last_nonce);
+    }
+
+    /// Checks that the nonce proposes an active edge and was created
+    /// within `EDGE_NONCE_MAX_TIME_DELTA` from now.
+    pub fn verify_nonce(&self, nonce: u64) -> Result<(), HandshakeError> {
+        if nonce.is_multiple_of(2) {
+            return Err(HandshakeError::InvalidNonce(nonce));
+        }
+
+        match Edge::nonce_to_utc(nonce) {
+            Ok(Some(nonce_time)) => {
+                if (time::Utc::now_utc() - nonce_time).abs() >= EDGE_NONCE_MAX_TIME_DELTA {
+                    Err(HandshakeError::NonceExpired(nonce))
+                } else {
+                    Ok(())
+                }
+            }
+            // Nonce isn't a timestamp.
+            Ok(None) | Err(_) => Err(HandshakeError::InvalidNonce(nonce)),
+        }
+    }
+
+    /// Verifies the nonce proposed by the peer and remembers it,
+    /// so that the peer can't reuse it or fall back to an older one.
+    pub fn accept_nonce(&self, peer_id: &PeerId, nonce: u64) -> Result<(), HandshakeError> {
+        self.verify_nonce(nonce)?;
+
+        let mut edge_nonces = self.edge_nonces.lock().unwrap();
+        match edge_nonces.get(peer_id) {
+            Some(&last_nonce) if last_nonce >= nonce => {
+                Err(HandshakeError::NonceReused { nonce, last_nonce })
+            }
+            _ => {
+                edge_nonces.insert(peer_id.clone(), nonce);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn peer_table(&self) -> MutexGuard<'_, PeerTable> {
+        self.peer_table.lock().unwrap()
+    }
+
     pub fn create_handshake(&self, target_peer_id: PeerId, nonce: u64) -> Handshake {
         let sender_peer_id = self.peer_id();
         let sender_secret_key = self.secret_key();
@@ -63,50 +249,256 @@ impl Node {
             sender_listen_port: self.sender_listen_port.into(),
             sender_chain_info: self.peer_chain_info.clone(),
             partial_edge_info,
+            owned_account: self.create_owned_account(),
         }
     }
 
-    pub fn verify_handshake(&self, target_handshake: &Handshake) -> bool {
-        if target_handshake.protocol_version < self.protocol_version {
-            println!("Wrong protocol version");
-            return false;
+    /// Proof of owning the validator key, `None` if the node isn't a validator.
+    pub fn create_owned_account(&self) -> Option<SignedOwnedAccount> {
+        let validator_key = self.validator_key.as_ref()?;
+
+        let owned_account = OwnedAccount {
+            account_key: validator_key.public_key(),
+            peer_id: self.peer_id(),
+            timestamp: time::Utc::now_utc(),
         };
-        if target_handshake.oldest_supported_version < self.oldest_supported_version {
-            println!("Wrong oldest supported protocol version");
-            return false;
+        Some(owned_account.sign(validator_key))
+    }
+
+    /// Checks the validator's proof of owning the account key, if the handshake carries one.
+    /// Its signature is verified when the handshake is decoded.
+    pub fn verify_owned_account(&self, target_handshake: &Handshake) -> Result<(), HandshakeError> {
+        let Some(owned_account) = &target_handshake.owned_account else {
+            return Ok(());
         };
+        let owned_account = owned_account.owned_account();
+
+        if owned_account.peer_id != target_handshake.sender_peer_id {
+            return Err(HandshakeError::PeerIdMismatch {
+                expected: target_handshake.sender_peer_id.clone(),
+                received: owned_account.peer_id.clone(),
+            });
+        }
+
+        if (time::Utc::now_utc() - owned_account.timestamp).abs() >= OWNED_ACCOUNT_MAX_CLOCK_SKEW {
+            return Err(HandshakeError::OwnedAccountExpired);
+        }
+
+        Ok(())
+    }
+
+    /// TIER1 connections are reserved for validators,
+    /// so the handshake has to prove owning a validator key.
+    pub fn verify_tier1_handshake(
+        &self,
+        target_handshake: &Handshake,
+    ) -> Result<(), HandshakeError> {
+        if target_handshake.owned_account.is_none() {
+            return Err(HandshakeError::MissingOwnedAccount);
+        }
+
+        self.verify_owned_account(target_handshake)
+    }
+
+    pub fn peer_info(&self) -> PeerInfo {
+        PeerInfo {
+            id: self.peer_id(),
+            addr: None,
+            account_id: None,
+        }
+    }
+
+    /// Returns the newest protocol version supported by both nodes
+    /// or `None` if `[oldest_supported_version, protocol_version]` ranges don't overlap.
+    pub fn negotiate_protocol_version(
+        &self,
+        protocol_version: u32,
+        oldest_supported_version: u32,
+    ) -> Option<u32> {
+        let agreed_version = self.protocol_version.min(protocol_version);
+        let oldest_version = self.oldest_supported_version.max(oldest_supported_version);
+
+        (oldest_version <= agreed_version).then_some(agreed_version)
+    }
+
+    /// Checks that the handshake is addressed to this node and that both nodes can talk
+    /// to each other. The returned reason is meant to be sent back in `HandshakeFailure`.
+    pub fn verify_handshake(
+        &self,
+        target_handshake: &Handshake,
+    ) -> Result<Session, HandshakeFailureReason> {
         if target_handshake.target_peer_id != self.peer_id() {
-            println!("Wrong target peer id");
-            return false;
+            return Err(HandshakeFailureReason::InvalidTarget);
         };
 
         if target_handshake.sender_chain_info.genesis_id != self.peer_chain_info.genesis_id {
-            println!("Wrong peer genesis id");
-            return false;
+            return Err(HandshakeFailureReason::GenesisMismatch(
+                self.peer_chain_info.genesis_id.clone(),
+            ));
         };
 
-        let (sender_peer_id, target_peer_id) = Edge::make_key(
-            target_handshake.sender_peer_id.clone(),
-            target_handshake.target_peer_id.clone(),
-        );
-        let edge_data = Edge::build_hash(
-            &sender_peer_id,
-            &target_peer_id,
-            target_handshake.partial_edge_info.nonce,
-        );
+        let protocol_version = self
+            .negotiate_protocol_version(
+                target_handshake.protocol_version,
+                target_handshake.oldest_supported_version,
+            )
+            .ok_or(HandshakeFailureReason::ProtocolVersionMismatch {
+                version: self.protocol_version,
+                oldest_supported_version: self.oldest_supported_version,
+            })?;
+
+        Ok(Session {
+            peer_id: target_handshake.sender_peer_id.clone(),
+            protocol_version,
+            peer_chain_info: target_handshake.sender_chain_info.clone(),
+            edge: self.build_edge(target_handshake),
+        })
+    }
+
+    /// Checks the sender's signature of the edge proposed to the handshake target.
+    /// Peers with invalid signature don't deserve a `HandshakeFailure` reply,
+    /// handshakes addressed to another node get one from `verify_handshake`.
+    pub fn verify_handshake_signature(&self, target_handshake: &Handshake) -> bool {
+        // Edge connects two distinct peers.
+        target_handshake.sender_peer_id != target_handshake.target_peer_id
+            && target_handshake.sender_peer_id != self.peer_id()
+            && Edge::partial_verify(
+                &target_handshake.target_peer_id,
+                &target_handshake.sender_peer_id,
+                &target_handshake.partial_edge_info,
+            )
+    }
+
+    /// Completes the edge proposed in the peer's handshake with this node's signature.
+    pub fn build_edge(&self, target_handshake: &Handshake) -> Edge {
+        self.complete_edge(
+            &target_handshake.sender_peer_id,
+            &target_handshake.partial_edge_info,
+        )
+    }
+
+    /// Completes the edge proposed by the peer with this node's signature.
+    pub fn complete_edge(&self, peer_id: &PeerId, partial_edge_info: &PartialEdgeInfo) -> Edge {
+        Edge::build_with_secret_key(
+            self.peer_id(),
+            peer_id.clone(),
+            partial_edge_info.nonce,
+            &self.secret_key(),
+            partial_edge_info.signature.clone(),
+        )
+    }
+
+    /// Proposes a fresh nonce for the edge of the session, to be sent in `RequestUpdateNonce`.
+    pub fn create_update_nonce_request(&self, session: &Session) -> PartialEdgeInfo {
+        let nonce = self.create_nonce(&session.peer_id);
+        PartialEdgeInfo::new(&self.peer_id(), &session.peer_id, nonce, &self.secret_key())
+    }
 
-        if !target_handshake.partial_edge_info.signature.verify(
-            edge_data.as_ref(),
-            target_handshake.sender_peer_id.public_key(),
-        ) {
-            println!("Wrong peer signature");
-            return false;
+    /// Answers the peer's `RequestUpdateNonce` with the co-signed edge,
+    /// which replaces the edge of the session.
+    pub fn respond_update_nonce(
+        &self,
+        session: &mut Session,
+        partial_edge_info: &PartialEdgeInfo,
+    ) -> Result<Edge, HandshakeError> {
+        let edge = self.complete_edge(&session.peer_id, partial_edge_info);
+        if !edge.verify() {
+            return Err(HandshakeError::InvalidSignature);
+        }
+        if edge.nonce() <= session.edge.nonce() {
+            return Err(HandshakeError::NonceReused {
+                nonce: edge.nonce(),
+                last_nonce: session.edge.nonce(),
+            });
         }
+        self.accept_nonce(&session.peer_id, edge.nonce())?;
 
-        true
+        session.edge = edge.clone();
+        Ok(edge)
     }
 
-    pub fn create_ping(&self, target_peer_id: PeerId) -> RoutedMessageV2 {
+    /// Verifies the peer's `ResponseUpdateNonce` to the `sent` request
+    /// and replaces the edge of the session.
+    pub fn accept_update_nonce_response(
+        &self,
+        session: &mut Session,
+        sent: &PartialEdgeInfo,
+        edge: &Edge,
+    ) -> Result<(), HandshakeError> {
+        if edge.key() != session.edge.key() {
+            return Err(HandshakeError::PeerIdMismatch {
+                expected: session.peer_id.clone(),
+                received: edge.other(&self.peer_id()).unwrap_or(&edge.key().0).clone(),
+            });
+        }
+
+        if edge.nonce() != sent.nonce {
+            return Err(HandshakeError::NonceMismatch {
+                expected: sent.nonce,
+                received: edge.nonce(),
+            });
+        }
+
+        if !edge.verify() {
+            return Err(HandshakeError::InvalidSignature);
+        }
+
+        self.record_nonce(&session.peer_id, edge.nonce());
+        session.edge = edge.clone();
+        Ok(())
+    }
+
+    /// Verifies the responder's handshake received on an outbound connection
+    /// of `tier` in reply to the `sent` handshake.
+    pub fn verify_handshake_response(
+        &self,
+        tier: Tier,
+        sent: &Handshake,
+        peer_message: &PeerMessage,
+    ) -> Result<Session, HandshakeError> {
+        let received = match (tier, peer_message) {
+            (Tier::Tier1, PeerMessage::Tier1Handshake(handshake))
+            | (Tier::Tier2, PeerMessage::Tier2Handshake(handshake)) => handshake,
+            (_, PeerMessage::HandshakeFailure(_, reason)) => {
+                return Err(HandshakeError::Rejected(reason.clone()))
+            }
+            (_, peer_message) => {
+                return Err(HandshakeError::UnexpectedMessage(peer_message.kind()))
+            }
+        };
+
+        if received.sender_peer_id != sent.target_peer_id {
+            return Err(HandshakeError::PeerIdMismatch {
+                expected: sent.target_peer_id.clone(),
+                received: received.sender_peer_id.clone(),
+            });
+        }
+
+        if received.partial_edge_info.nonce != sent.partial_edge_info.nonce {
+            return Err(HandshakeError::NonceMismatch {
+                expected: sent.partial_edge_info.nonce,
+                received: received.partial_edge_info.nonce,
+            });
+        }
+
+        if !self.verify_handshake_signature(received) {
+            return Err(HandshakeError::InvalidSignature);
+        }
+
+        match tier {
+            Tier::Tier1 => self.verify_tier1_handshake(received)?,
+            Tier::Tier2 => self.verify_owned_account(received)?,
+        }
+
+        let session = self
+            .verify_handshake(received)
+            .map_err(HandshakeError::Invalid)?;
+        self.record_nonce(&session.peer_id, sent.partial_edge_info.nonce);
+
+        Ok(session)
+    }
+
+    pub fn create_ping(&self, target_peer_id: PeerId) -> RoutedMessage {
         let routed_message_body = RoutedMessageBody::Ping(Ping {
             nonce: 3,
             source: self.peer_id(),
@@ -117,13 +509,16 @@ impl Node {
             body: routed_message_body,
         };
 
-        let routed_message =
-            raw_routed_message.sign(&self.secret_key(), 100, Some(time::Utc::now_utc()));
-
-        routed_message
+        raw_routed_message
+            .sign(
+                &self.secret_key(),
+                self.routed_message_ttl,
+                Some(time::Utc::now_utc()),
+            )
+            .into()
     }
 
-    pub fn create_pong(&self, target_peer_id: PeerId, nonce: u64) -> RoutedMessageV2 {
+    pub fn create_pong(&self, target_peer_id: PeerId, nonce: u64) -> RoutedMessage {
         let routed_message_body = RoutedMessageBody::Pong(Pong {
             nonce,
             source: self.peer_id(),
@@ -134,18 +529,23 @@ impl Node {
             body: routed_message_body,
         };
 
-        let routed_message =
-            raw_routed_message.sign(&self.secret_key(), 100, Some(time::Utc::now_utc()));
-
-        routed_message
+        raw_routed_message
+            .sign(
+                &self.secret_key(),
+                self.routed_message_ttl,
+                Some(time::Utc::now_utc()),
+            )
+            .into()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
+    use clap::Parser;
     use ed25519_dalek::Keypair;
-    use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, SecretKey};
+    use near_crypto::{ED25519PublicKey, ED25519SecretKey, KeyFile, PublicKey, SecretKey};
+    use near_network_primitives::time;
     use near_network_primitives::types::{PartialEdgeInfo, PeerChainInfoV2};
     use near_primitives::block::GenesisId;
     use near_primitives::borsh::BorshDeserialize;
@@ -153,9 +553,15 @@ mod tests {
     use near_primitives::network::PeerId;
     use rand::rngs::OsRng;
 
+    use crate::config::Config;
+    use crate::error::HandshakeError;
     use crate::proto::network;
-    use crate::types::handshake::Handshake;
-    use crate::types::node::Node;
+    use crate::types::account_key::OwnedAccount;
+    use crate::types::handshake::{Handshake, Tier};
+    use crate::types::handshake_failure::HandshakeFailureReason;
+    use crate::types::node::{Node, EDGE_NONCE_MAX_TIME_DELTA, OWNED_ACCOUNT_MAX_CLOCK_SKEW};
+    use crate::types::peer_message::PeerMessage;
+    use crate::types::session::Session;
 
     #[test]
     fn test_create_handshake() -> Result<()> {
@@ -173,20 +579,8 @@ mod tests {
         };
 
         let (target_node, sender_node) = (
-            Node {
-                key_pair: Keypair::generate(&mut OsRng),
-                protocol_version: 0,
-                oldest_supported_version: 0,
-                sender_listen_port: 0,
-                peer_chain_info: sender_chain_info.clone(),
-            },
-            Node {
-                key_pair: Keypair::generate(&mut OsRng),
-                protocol_version: 0,
-                oldest_supported_version: 0,
-                sender_listen_port: 0,
-                peer_chain_info: sender_chain_info.clone(),
-            },
+            Node::new_test(sender_chain_info.clone()),
+            Node::new_test(sender_chain_info.clone()),
         );
 
         let partial_edge_info = PartialEdgeInfo::new(
@@ -211,6 +605,7 @@ mod tests {
             sender_listen_port: Some(51200),
             sender_chain_info,
             partial_edge_info,
+            owned_account: None,
         };
 
         let handshake_original = handshake.clone();
@@ -220,4 +615,458 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_verify_handshake() -> Result<()> {
+        let genesis_id = GenesisId {
+            chain_id: "testnet".to_string(),
+            hash: CryptoHash::try_from_slice(
+                ed25519_dalek::SecretKey::generate(&mut OsRng).as_bytes(),
+            )?,
+        };
+        let peer_chain_info = PeerChainInfoV2 {
+            genesis_id: genesis_id.clone(),
+            height: 0,
+            tracked_shards: vec![],
+            archival: false,
+        };
+
+        let (target_node, sender_node) = (
+            Node::new_test(peer_chain_info.clone()),
+            Node::new_test(peer_chain_info),
+        );
+
+        let handshake = sender_node.create_handshake(target_node.peer_id(), 1);
+        assert!(target_node.verify_handshake_signature(&handshake));
+        assert_eq!(
+            target_node.verify_handshake(&handshake),
+            Ok(Session {
+                peer_id: sender_node.peer_id(),
+                protocol_version: 63,
+                peer_chain_info: sender_node.peer_chain_info.clone(),
+                edge: target_node.build_edge(&handshake),
+            })
+        );
+
+        let wrong_target = sender_node.create_handshake(
+            PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+                Keypair::generate(&mut OsRng).public.to_bytes(),
+            ))),
+            1,
+        );
+        assert_eq!(
+            target_node.verify_handshake(&wrong_target),
+            Err(HandshakeFailureReason::InvalidTarget)
+        );
+        // Signed by the sender, so it's answered with `HandshakeFailure`.
+        assert!(target_node.verify_handshake_signature(&wrong_target));
+
+        let mut wrong_genesis = handshake;
+        wrong_genesis.sender_chain_info.genesis_id.chain_id = "mainnet".to_string();
+        assert_eq!(
+            target_node.verify_handshake(&wrong_genesis),
+            Err(HandshakeFailureReason::GenesisMismatch(genesis_id))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_negotiate_protocol_version() -> Result<()> {
+        let genesis_id = GenesisId {
+            chain_id: "testnet".to_string(),
+            hash: CryptoHash::try_from_slice(
+                ed25519_dalek::SecretKey::generate(&mut OsRng).as_bytes(),
+            )?,
+        };
+        let peer_chain_info = PeerChainInfoV2 {
+            genesis_id,
+            height: 0,
+            tracked_shards: vec![],
+            archival: false,
+        };
+        let node = |protocol_version, oldest_supported_version| Node {
+            protocol_version,
+            oldest_supported_version,
+            ..Node::new_test(peer_chain_info.clone())
+        };
+
+        let target_node = node(63, 61);
+        let mismatch = Err(HandshakeFailureReason::ProtocolVersionMismatch {
+            version: 63,
+            oldest_supported_version: 61,
+        });
+
+        for (protocol_version, oldest_supported_version, expected) in [
+            // Older peer.
+            (62, 58, Ok(62)),
+            (61, 55, Ok(61)),
+            // Newer peer.
+            (65, 62, Ok(63)),
+            (70, 63, Ok(63)),
+            // Disjoint ranges.
+            (60, 55, mismatch.clone()),
+            (70, 64, mismatch.clone()),
+        ] {
+            let sender_node = node(protocol_version, oldest_supported_version);
+            let handshake = sender_node.create_handshake(target_node.peer_id(), 1);
+
+            assert_eq!(
+                target_node
+                    .verify_handshake(&handshake)
+                    .map(|session| session.protocol_version),
+                expected
+            );
+            assert_eq!(
+                sender_node.negotiate_protocol_version(63, 61),
+                expected.ok()
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_handshake_response() -> Result<()> {
+        let genesis_id = GenesisId {
+            chain_id: "testnet".to_string(),
+            hash: CryptoHash::try_from_slice(
+                ed25519_dalek::SecretKey::generate(&mut OsRng).as_bytes(),
+            )?,
+        };
+        let peer_chain_info = PeerChainInfoV2 {
+            genesis_id,
+            height: 0,
+            tracked_shards: vec![],
+            archival: false,
+        };
+        let node = || Node::new_test(peer_chain_info.clone());
+        let (sender_node, target_node, other_node) = (node(), node(), node());
+
+        let sent = sender_node.create_handshake(target_node.peer_id(), 7);
+        let response = target_node.create_handshake(sender_node.peer_id(), 7);
+
+        let session = sender_node
+            .verify_handshake_response(
+                Tier::Tier2,
+                &sent,
+                &PeerMessage::Tier2Handshake(response.clone()),
+            )
+            .unwrap();
+        assert_eq!(
+            session,
+            Session {
+                peer_id: target_node.peer_id(),
+                protocol_version: 63,
+                peer_chain_info: peer_chain_info.clone(),
+                edge: sender_node.build_edge(&response),
+            }
+        );
+        assert!(session.edge.verify());
+        assert_eq!(
+            session.edge,
+            target_node.verify_handshake(&sent).unwrap().edge
+        );
+
+        let stale_nonce = target_node.create_handshake(sender_node.peer_id(), 5);
+        assert_eq!(
+            sender_node.verify_handshake_response(
+                Tier::Tier2,
+                &sent,
+                &PeerMessage::Tier2Handshake(stale_nonce)
+            ),
+            Err(HandshakeError::NonceMismatch {
+                expected: 7,
+                received: 5,
+            })
+        );
+
+        let other_peer = other_node.create_handshake(sender_node.peer_id(), 7);
+        assert_eq!(
+            sender_node.verify_handshake_response(
+                Tier::Tier2,
+                &sent,
+                &PeerMessage::Tier2Handshake(other_peer)
+            ),
+            Err(HandshakeError::PeerIdMismatch {
+                expected: target_node.peer_id(),
+                received: other_node.peer_id(),
+            })
+        );
+
+        let mut forged = response.clone();
+        forged.partial_edge_info = other_node
+            .create_handshake(sender_node.peer_id(), 7)
+            .partial_edge_info;
+        assert_eq!(
+            sender_node.verify_handshake_response(
+                Tier::Tier2,
+                &sent,
+                &PeerMessage::Tier2Handshake(forged)
+            ),
+            Err(HandshakeError::InvalidSignature)
+        );
+
+        let rejected = PeerMessage::HandshakeFailure(
+            target_node.peer_info(),
+            HandshakeFailureReason::InvalidTarget,
+        );
+        assert_eq!(
+            sender_node.verify_handshake_response(Tier::Tier2, &sent, &rejected),
+            Err(HandshakeError::Rejected(
+                HandshakeFailureReason::InvalidTarget
+            ))
+        );
+
+        let unexpected = PeerMessage::Tier1Handshake(response.clone());
+        assert_eq!(
+            sender_node.verify_handshake_response(Tier::Tier2, &sent, &unexpected),
+            Err(HandshakeError::UnexpectedMessage("Tier1Handshake"))
+        );
+        let unexpected = PeerMessage::Tier2Handshake(response.clone());
+        assert_eq!(
+            sender_node.verify_handshake_response(Tier::Tier1, &sent, &unexpected),
+            Err(HandshakeError::UnexpectedMessage("Tier2Handshake"))
+        );
+        assert_eq!(
+            sender_node.verify_handshake_response(
+                Tier::Tier1,
+                &sent,
+                &PeerMessage::Tier1Handshake(response)
+            ),
+            Err(HandshakeError::MissingOwnedAccount)
+        );
+
+        let validator_node = Node {
+            validator_key: Some(SecretKey::ED25519(ED25519SecretKey(
+                Keypair::generate(&mut OsRng).to_bytes(),
+            ))),
+            ..node()
+        };
+        let sent = sender_node.create_handshake(validator_node.peer_id(), 9);
+        let response = validator_node.create_handshake(sender_node.peer_id(), 9);
+        let session = sender_node
+            .verify_handshake_response(Tier::Tier1, &sent, &PeerMessage::Tier1Handshake(response))
+            .unwrap();
+        assert_eq!(session.peer_id, validator_node.peer_id());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nonce() {
+        let node = Node::new_test(PeerChainInfoV2::default());
+        let peer_id = PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+            Keypair::generate(&mut OsRng).public.to_bytes(),
+        )));
+
+        let nonce = node.create_nonce(&node.peer_id());
+        assert_eq!(nonce % 2, 1);
+        assert_eq!(node.verify_nonce(nonce), Ok(()));
+        // Nonces are remembered only once the handshake is accepted,
+        // the next edge within the same second gets a newer nonce.
+        assert_eq!(node.create_nonce(&node.peer_id()), nonce);
+        node.record_nonce(&node.peer_id(), nonce);
+        assert_eq!(node.create_nonce(&node.peer_id()), nonce + 2);
+
+        // Old style and removed edge nonces.
+        assert_eq!(node.verify_nonce(1), Err(HandshakeError::InvalidNonce(1)));
+        assert_eq!(
+            node.verify_nonce(nonce + 1),
+            Err(HandshakeError::InvalidNonce(nonce + 1))
+        );
+
+        let stale_nonce = nonce - 2 * EDGE_NONCE_MAX_TIME_DELTA.whole_seconds() as u64;
+        assert_eq!(
+            node.verify_nonce(stale_nonce),
+            Err(HandshakeError::NonceExpired(stale_nonce))
+        );
+        let future_nonce = nonce + 2 * EDGE_NONCE_MAX_TIME_DELTA.whole_seconds() as u64;
+        assert_eq!(
+            node.verify_nonce(future_nonce),
+            Err(HandshakeError::NonceExpired(future_nonce))
+        );
+
+        assert_eq!(node.accept_nonce(&peer_id, nonce - 2), Ok(()));
+        assert_eq!(node.accept_nonce(&peer_id, nonce), Ok(()));
+        assert_eq!(
+            node.accept_nonce(&peer_id, nonce),
+            Err(HandshakeError::NonceReused {
+                nonce,
+                last_nonce: nonce,
+            })
+        );
+        assert_eq!(
+            node.accept_nonce(&peer_id, nonce - 2),
+            Err(HandshakeError::NonceReused {
+                nonce: nonce - 2,
+                last_nonce: nonce,
+            })
+        );
+    }
+
+    #[test]
+    fn test_node_key() -> Result<()> {
+        let node_key = std::env::temp_dir().join(format!("node_key_{}.json", std::process::id()));
+        let config = Config::parse_from([
+            "node_handshake",
+            "--network=localnet",
+            "--target-peer-info=ed25519:Kmpx1xn2mtLPchDPLyTr9sgyf4HFfdeKFfKwqw8HJC4@127.0.0.1:24567",
+            &format!("--node-key={}", node_key.display()),
+        ]);
+
+        let generated_node = Node::try_from(config.clone())?;
+        let key_file = KeyFile::from_file(&node_key)?;
+        assert_eq!(key_file.account_id.as_str(), "node");
+        assert_eq!(&key_file.public_key, generated_node.peer_id().public_key());
+        assert_eq!(key_file.secret_key, generated_node.secret_key());
+
+        let loaded_node = Node::try_from(config)?;
+        assert_eq!(loaded_node.peer_id(), generated_node.peer_id());
+        assert_eq!(loaded_node.secret_key(), generated_node.secret_key());
+
+        std::fs::remove_file(node_key)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_nonce() -> Result<()> {
+        let node = || Node::new_test(PeerChainInfoV2::default());
+        let (sender_node, target_node, other_node) = (node(), node(), node());
+
+        let nonce = sender_node.create_nonce(&target_node.peer_id()) - 10;
+        let sent = sender_node.create_handshake(target_node.peer_id(), nonce);
+        let response = target_node.create_handshake(sender_node.peer_id(), nonce);
+        let mut sender_session = sender_node
+            .verify_handshake_response(Tier::Tier2, &sent, &PeerMessage::Tier2Handshake(response))
+            .unwrap();
+        let mut target_session = target_node.verify_handshake(&sent).unwrap();
+
+        // Nonce older than the one of the session edge.
+        let older = PartialEdgeInfo::new(
+            &sender_node.peer_id(),
+            &target_node.peer_id(),
+            nonce - 2,
+            &sender_node.secret_key(),
+        );
+        assert_eq!(
+            target_node.respond_update_nonce(&mut target_session, &older),
+            Err(HandshakeError::NonceReused {
+                nonce: nonce - 2,
+                last_nonce: nonce,
+            })
+        );
+        assert_eq!(target_session.edge.nonce(), nonce);
+
+        let request = sender_node.create_update_nonce_request(&sender_session);
+        assert!(request.nonce > nonce);
+
+        let edge = target_node
+            .respond_update_nonce(&mut target_session, &request)
+            .unwrap();
+        assert!(edge.verify());
+        assert_eq!(edge.nonce(), request.nonce);
+        assert_eq!(target_session.edge, edge);
+
+        let refreshed_edge = target_session.edge.clone();
+        assert_eq!(
+            sender_node.accept_update_nonce_response(&mut sender_session, &request, &edge),
+            Ok(())
+        );
+        assert_eq!(sender_session.edge, edge);
+
+        // Replayed request.
+        assert_eq!(
+            target_node.respond_update_nonce(&mut target_session, &request),
+            Err(HandshakeError::NonceReused {
+                nonce: request.nonce,
+                last_nonce: request.nonce,
+            })
+        );
+
+        let forged = PartialEdgeInfo {
+            signature: other_node
+                .create_update_nonce_request(&target_session)
+                .signature,
+            ..request.clone()
+        };
+        assert_eq!(
+            target_node.respond_update_nonce(&mut target_session, &forged),
+            Err(HandshakeError::InvalidSignature)
+        );
+
+        let other_request = sender_node.create_update_nonce_request(&sender_session);
+        assert_eq!(
+            sender_node.accept_update_nonce_response(
+                &mut sender_session,
+                &other_request,
+                &refreshed_edge
+            ),
+            Err(HandshakeError::NonceMismatch {
+                expected: other_request.nonce,
+                received: refreshed_edge.nonce(),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_owned_account() -> Result<()> {
+        let validator_key =
+            SecretKey::ED25519(ED25519SecretKey(Keypair::generate(&mut OsRng).to_bytes()));
+        let node = |validator_key| Node {
+            validator_key,
+            ..Node::new_test(PeerChainInfoV2::default())
+        };
+        let (validator_node, target_node) = (node(Some(validator_key.clone())), node(None));
+
+        let handshake = validator_node.create_handshake(target_node.peer_id(), 1);
+        let network_peer_message: network::PeerMessage =
+            PeerMessage::Tier1Handshake(handshake.clone()).into();
+        let PeerMessage::Tier1Handshake(handshake) = network_peer_message.try_into()? else {
+            unreachable!("encoded as Tier1Handshake")
+        };
+        assert_eq!(
+            handshake
+                .owned_account
+                .as_ref()
+                .unwrap()
+                .owned_account()
+                .account_key,
+            validator_key.public_key()
+        );
+        assert_eq!(target_node.verify_tier1_handshake(&handshake), Ok(()));
+
+        let mut handshake = target_node.create_handshake(validator_node.peer_id(), 1);
+        assert_eq!(handshake.owned_account, None);
+        assert_eq!(validator_node.verify_owned_account(&handshake), Ok(()));
+        assert_eq!(
+            validator_node.verify_tier1_handshake(&handshake),
+            Err(HandshakeError::MissingOwnedAccount)
+        );
+
+        handshake.owned_account = validator_node.create_owned_account();
+        assert_eq!(
+            validator_node.verify_owned_account(&handshake),
+            Err(HandshakeError::PeerIdMismatch {
+                expected: target_node.peer_id(),
+                received: validator_node.peer_id(),
+            })
+        );
+
+        let mut handshake = validator_node.create_handshake(target_node.peer_id(), 1);
+        let expired = OwnedAccount {
+            account_key: validator_key.public_key(),
+            peer_id: validator_node.peer_id(),
+            timestamp: time::Utc::now_utc() - OWNED_ACCOUNT_MAX_CLOCK_SKEW * 2,
+        };
+        handshake.owned_account = Some(expired.sign(&validator_key));
+        assert_eq!(
+            target_node.verify_owned_account(&handshake),
+            Err(HandshakeError::OwnedAccountExpired)
+        );
+
+        Ok(())
+    }
 }
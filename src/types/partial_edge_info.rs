@@ -1,6 +1,7 @@
 use near_network_primitives::types::PartialEdgeInfo;
 use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
 
+use crate::error::Error;
 use crate::proto;
 
 impl From<PartialEdgeInfo> for proto::network::PartialEdgeInfo {
@@ -13,10 +14,31 @@ impl From<PartialEdgeInfo> for proto::network::PartialEdgeInfo {
 }
 
 impl TryFrom<proto::network::PartialEdgeInfo> for PartialEdgeInfo {
-    type Error = std::io::Error;
+    type Error = Error;
 
     fn try_from(value: proto::network::PartialEdgeInfo) -> Result<Self, Self::Error> {
-        Self::try_from_slice(&value.borsh)
+        Self::try_from_slice(&value.borsh).map_err(Error::Borsh)
+    }
+}
+
+impl From<PartialEdgeInfo> for proto::network::UpdateNonceRequest {
+    fn from(value: PartialEdgeInfo) -> Self {
+        Self {
+            partial_edge_info: Some(value.into()).into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::UpdateNonceRequest> for PartialEdgeInfo {
+    type Error = Error;
+
+    fn try_from(value: proto::network::UpdateNonceRequest) -> Result<Self, Self::Error> {
+        value
+            .partial_edge_info
+            .into_option()
+            .ok_or(Error::MissingField("partial_edge_info"))?
+            .try_into()
     }
 }
 
@@ -50,6 +72,12 @@ mod tests {
             network_partial_edge_info.try_into().unwrap();
         assert_eq!(partial_edge_info_original, partial_edge_info_restored);
 
+        let network_update_nonce_request: network::UpdateNonceRequest =
+            partial_edge_info_original.clone().into();
+        let update_nonce_request_restored: PartialEdgeInfo =
+            network_update_nonce_request.try_into()?;
+        assert_eq!(partial_edge_info_original, update_nonce_request_restored);
+
         Ok(())
     }
 }
@@ -0,0 +1,117 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use near_primitives::network::PeerId;
+
+use crate::error::Error;
+use crate::proto;
+
+/// Address of a peer, e.g. of a TIER1 proxy.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PeerAddr {
+    pub addr: SocketAddr,
+    pub peer_id: PeerId,
+}
+
+impl From<SocketAddr> for proto::network::SocketAddr {
+    fn from(value: SocketAddr) -> Self {
+        Self {
+            ip: match value.ip() {
+                IpAddr::V4(ip) => ip.octets().to_vec(),
+                IpAddr::V6(ip) => ip.octets().to_vec(),
+            },
+            port: value.port().into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::SocketAddr> for SocketAddr {
+    type Error = Error;
+
+    fn try_from(value: proto::network::SocketAddr) -> Result<Self, Self::Error> {
+        let ip = if let Ok(ip) = <[u8; 4]>::try_from(value.ip.as_slice()) {
+            IpAddr::V4(Ipv4Addr::from(ip))
+        } else if let Ok(ip) = <[u8; 16]>::try_from(value.ip.as_slice()) {
+            IpAddr::V6(Ipv6Addr::from(ip))
+        } else {
+            return Err(Error::InvalidField("ip"));
+        };
+        let port = u16::try_from(value.port).map_err(|_| Error::InvalidField("port"))?;
+
+        Ok(SocketAddr::new(ip, port))
+    }
+}
+
+impl From<PeerAddr> for proto::network::PeerAddr {
+    fn from(value: PeerAddr) -> Self {
+        Self {
+            addr: Some(value.addr.into()).into(),
+            peer_id: Some(value.peer_id.into()).into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::PeerAddr> for PeerAddr {
+    type Error = Error;
+
+    fn try_from(value: proto::network::PeerAddr) -> Result<Self, Self::Error> {
+        Ok(Self {
+            addr: value
+                .addr
+                .into_option()
+                .ok_or(Error::MissingField("addr"))?
+                .try_into()?,
+            peer_id: value
+                .peer_id
+                .into_option()
+                .ok_or(Error::MissingField("peer_id"))?
+                .try_into()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    use anyhow::Result;
+    use near_crypto::{ED25519PublicKey, PublicKey};
+    use near_primitives::network::PeerId;
+    use rand::rngs::OsRng;
+
+    use crate::error::Error;
+    use crate::proto::network;
+    use crate::types::peer_addr::PeerAddr;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let peer_id = PeerId::new(PublicKey::ED25519(ED25519PublicKey::from(
+            ed25519_dalek::SecretKey::generate(&mut OsRng).to_bytes(),
+        )));
+
+        for addr in [
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 24567)),
+            SocketAddr::from((Ipv6Addr::LOCALHOST, 24567)),
+        ] {
+            let peer_addr = PeerAddr {
+                addr,
+                peer_id: peer_id.clone(),
+            };
+            let peer_addr_original = peer_addr.clone();
+            let network_peer_addr: network::PeerAddr = peer_addr.into();
+            let peer_addr_restored: PeerAddr = network_peer_addr.try_into()?;
+            assert_eq!(peer_addr_original, peer_addr_restored);
+        }
+
+        let network_socket_addr = network::SocketAddr {
+            ip: vec![127, 0, 0],
+            port: 24567,
+            ..Default::default()
+        };
+        let error = SocketAddr::try_from(network_socket_addr).unwrap_err();
+        assert!(matches!(error, Error::InvalidField("ip")));
+
+        Ok(())
+    }
+}
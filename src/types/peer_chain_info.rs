@@ -1,9 +1,7 @@
 use near_network_primitives::types::PeerChainInfoV2;
-use near_primitives::block::GenesisId;
-use near_primitives::borsh::BorshDeserialize;
-use near_primitives::hash::CryptoHash;
 use protobuf::MessageField;
 
+use crate::error::Error;
 use crate::proto;
 
 impl From<PeerChainInfoV2> for proto::network::PeerChainInfo {
@@ -19,15 +17,15 @@ impl From<PeerChainInfoV2> for proto::network::PeerChainInfo {
 }
 
 impl TryFrom<proto::network::PeerChainInfo> for PeerChainInfoV2 {
-    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Error = Error;
 
     fn try_from(value: proto::network::PeerChainInfo) -> Result<Self, Self::Error> {
-        let genesis_id = GenesisId {
-            chain_id: value.genesis_id.chain_id.clone(),
-            hash: CryptoHash::try_from_slice(value.genesis_id.hash.hash.as_slice())?,
-        };
         Ok(Self {
-            genesis_id,
+            genesis_id: value
+                .genesis_id
+                .into_option()
+                .ok_or(Error::MissingField("genesis_id"))?
+                .try_into()?,
             height: value.height,
             tracked_shards: value.tracked_shards,
             archival: value.archival,
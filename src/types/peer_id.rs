@@ -1,6 +1,7 @@
 use near_crypto::PublicKey;
 use near_primitives::network::PeerId;
 
+use crate::error::Error;
 use crate::proto;
 
 impl From<PeerId> for proto::network::PublicKey {
@@ -10,7 +11,7 @@ impl From<PeerId> for proto::network::PublicKey {
 }
 
 impl TryFrom<proto::network::PublicKey> for PeerId {
-    type Error = std::io::Error;
+    type Error = Error;
     fn try_from(value: proto::network::PublicKey) -> Result<Self, Self::Error> {
         PublicKey::try_from(value).map(Self::new)
     }
@@ -22,10 +23,11 @@ mod tests {
     use near_primitives::network::PeerId;
     use rand::rngs::OsRng;
 
+    use crate::error::Error;
     use crate::proto::network;
 
     #[test]
-    fn test_serde() -> std::io::Result<()> {
+    fn test_serde() -> Result<(), Error> {
         let peer_id = PeerId::new(PublicKey::ED25519(ED25519PublicKey::from(
             ed25519_dalek::SecretKey::generate(&mut OsRng).to_bytes(),
         )));
@@ -0,0 +1,53 @@
+use near_network_primitives::types::PeerInfo;
+use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::error::Error;
+use crate::proto;
+
+impl From<PeerInfo> for proto::network::PeerInfo {
+    fn from(value: PeerInfo) -> Self {
+        Self {
+            borsh: value.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::PeerInfo> for PeerInfo {
+    type Error = Error;
+
+    fn try_from(value: proto::network::PeerInfo) -> Result<Self, Self::Error> {
+        Self::try_from_slice(value.borsh.as_slice()).map_err(Error::Borsh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use anyhow::Result;
+    use near_crypto::{ED25519PublicKey, PublicKey};
+    use near_network_primitives::types::PeerInfo;
+    use near_primitives::network::PeerId;
+    use rand::rngs::OsRng;
+
+    use crate::proto::network;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let peer_id = PeerId::new(PublicKey::ED25519(ED25519PublicKey::from(
+            ed25519_dalek::SecretKey::generate(&mut OsRng).to_bytes(),
+        )));
+        let peer_info = PeerInfo {
+            id: peer_id,
+            addr: Some(SocketAddr::from((Ipv4Addr::LOCALHOST, 24567))),
+            account_id: Some("test.near".parse()?),
+        };
+        let peer_info_original = peer_info.clone();
+        let network_peer_info: network::PeerInfo = peer_info.into();
+        let peer_info_restored: PeerInfo = network_peer_info.try_into()?;
+        assert_eq!(peer_info_original, peer_info_restored);
+
+        Ok(())
+    }
+}
@@ -1,33 +1,25 @@
 use near_network_primitives::time;
 use near_network_primitives::time::Utc;
-use near_network_primitives::types::{
-    Edge, PartialEdgeInfo, PeerInfo, RoutedMessage, RoutedMessageV2,
-};
-use near_primitives::block::{Block, GenesisId};
+use near_network_primitives::types::{Edge, PartialEdgeInfo, PeerInfo};
+use near_primitives::block::Block;
 use near_primitives::block_header::BlockHeader;
-use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
 use near_primitives::challenge::Challenge;
 use near_primitives::hash::CryptoHash;
 use near_primitives::transaction::SignedTransaction;
 use protobuf::well_known_types::timestamp::Timestamp;
-use protobuf::MessageField;
+use protobuf::{Message, UnknownFields, UnknownValueRef};
 
+use crate::error::Error;
 use crate::proto;
 use crate::proto::network::peer_message::Message_type;
-use crate::proto::network::{
-    Disconnect, DistanceVector, PeersRequest, PeersResponse, RoutingTableUpdate, SyncAccountsData,
-};
-use crate::types::handshake::Handshake;
-
-#[derive(PartialEq, Eq, Clone, Debug)]
-pub enum HandshakeFailureReason {
-    ProtocolVersionMismatch {
-        version: u32,
-        oldest_supported_version: u32,
-    },
-    GenesisMismatch(GenesisId),
-    InvalidTarget,
-}
+use crate::types::disconnect::Disconnect;
+use crate::types::distance_vector::DistanceVector;
+use crate::types::handshake::{Handshake, Tier};
+use crate::types::handshake_failure::HandshakeFailureReason;
+use crate::types::peers::{PeersRequest, PeersResponse};
+use crate::types::routed_message::RoutedMessage;
+use crate::types::routing_table_update::RoutingTableUpdate;
+use crate::types::sync_accounts_data::SyncAccountsData;
 
 #[derive(PartialEq, Clone, Debug)]
 #[allow(clippy::large_enum_variant)]
@@ -40,7 +32,10 @@ pub enum PeerMessage {
     /// Contains accounts and edge information.
     SyncRoutingTable(RoutingTableUpdate),
     DistanceVector(DistanceVector),
+    /// Proposes a fresh nonce for the edge of the connection.
     RequestUpdateNonce(PartialEdgeInfo),
+    /// Edge with the nonce proposed in `RequestUpdateNonce`, signed by both peers.
+    ResponseUpdateNonce(Edge),
 
     SyncAccountsData(SyncAccountsData),
 
@@ -54,11 +49,66 @@ pub enum PeerMessage {
     Block(Block),
 
     Transaction(SignedTransaction),
-    Routed(Box<RoutedMessageV2>),
+    Routed(Box<RoutedMessage>),
 
     /// Gracefully disconnect from other peer.
     Disconnect(Disconnect),
     Challenge(Challenge),
+
+    /// Message of a type this crate doesn't know, e.g. introduced by a newer nearcore.
+    /// Keeps every field of the message, so that it can be forwarded without losing any.
+    Unknown {
+        /// Field number of the message type in the `PeerMessage` oneof.
+        kind: u32,
+        /// All fields of the message, including the message of the type.
+        unknown_fields: UnknownFields,
+    },
+}
+
+impl PeerMessage {
+    /// Wraps the handshake into the message type of `tier`.
+    pub fn handshake(tier: Tier, handshake: Handshake) -> Self {
+        match tier {
+            Tier::Tier1 => PeerMessage::Tier1Handshake(handshake),
+            Tier::Tier2 => PeerMessage::Tier2Handshake(handshake),
+        }
+    }
+
+    /// Name of the message type, for logs and errors.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PeerMessage::Tier1Handshake(_) => "Tier1Handshake",
+            PeerMessage::Tier2Handshake(_) => "Tier2Handshake",
+            PeerMessage::HandshakeFailure(_, _) => "HandshakeFailure",
+            PeerMessage::LastEdge(_) => "LastEdge",
+            PeerMessage::SyncRoutingTable(_) => "SyncRoutingTable",
+            PeerMessage::DistanceVector(_) => "DistanceVector",
+            PeerMessage::RequestUpdateNonce(_) => "RequestUpdateNonce",
+            PeerMessage::ResponseUpdateNonce(_) => "ResponseUpdateNonce",
+            PeerMessage::SyncAccountsData(_) => "SyncAccountsData",
+            PeerMessage::PeersRequest(_) => "PeersRequest",
+            PeerMessage::PeersResponse(_) => "PeersResponse",
+            PeerMessage::BlockHeadersRequest(_) => "BlockHeadersRequest",
+            PeerMessage::BlockHeaders(_) => "BlockHeaders",
+            PeerMessage::BlockRequest(_) => "BlockRequest",
+            PeerMessage::Block(_) => "Block",
+            PeerMessage::Transaction(_) => "Transaction",
+            PeerMessage::Routed(_) => "Routed",
+            PeerMessage::Disconnect(_) => "Disconnect",
+            PeerMessage::Challenge(_) => "Challenge",
+            PeerMessage::Unknown { .. } => "Unknown",
+        }
+    }
+
+    /// Decodes the protobuf encoded message.
+    /// Messages of unknown type are returned as `Unknown`.
+    pub fn parse_from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        proto::network::PeerMessage::parse_from_bytes(bytes)?.try_into()
+    }
+
+    pub fn write_to_bytes(self) -> Result<Vec<u8>, Error> {
+        Ok(proto::network::PeerMessage::from(self).write_to_bytes()?)
+    }
 }
 
 impl From<PeerMessage> for proto::network::PeerMessage {
@@ -71,18 +121,53 @@ impl From<PeerMessage> for proto::network::PeerMessage {
                 PeerMessage::Tier2Handshake(handshake) => {
                     Message_type::Tier2Handshake(handshake.into())
                 }
-                PeerMessage::Routed(routed_message) => {
-                    Message_type::Routed(proto::network::RoutedMessage {
-                        borsh: routed_message.msg.try_to_vec().unwrap(),
-                        created_at: MessageField::from_option(
-                            routed_message.created_at.as_ref().map(utc_to_proto),
-                        ),
-                        num_hops: None,
-                        ..Default::default()
-                    })
+                PeerMessage::HandshakeFailure(peer_info, reason) => {
+                    Message_type::HandshakeFailure((peer_info, reason).into())
+                }
+                PeerMessage::LastEdge(edge) => Message_type::LastEdge(edge.into()),
+                PeerMessage::SyncRoutingTable(routing_table_update) => {
+                    Message_type::SyncRoutingTable(routing_table_update.into())
+                }
+                PeerMessage::DistanceVector(distance_vector) => {
+                    Message_type::DistanceVector(distance_vector.into())
+                }
+                PeerMessage::RequestUpdateNonce(partial_edge_info) => {
+                    Message_type::UpdateNonceRequest(partial_edge_info.into())
+                }
+                PeerMessage::ResponseUpdateNonce(edge) => {
+                    Message_type::UpdateNonceResponse(edge.into())
+                }
+                PeerMessage::SyncAccountsData(sync_accounts_data) => {
+                    Message_type::SyncAccountsData(sync_accounts_data.into())
+                }
+                PeerMessage::PeersRequest(peers_request) => {
+                    Message_type::PeersRequest(peers_request.into())
+                }
+                PeerMessage::PeersResponse(peers_response) => {
+                    Message_type::PeersResponse(peers_response.into())
+                }
+                PeerMessage::BlockHeadersRequest(block_hashes) => {
+                    Message_type::BlockHeadersRequest(block_hashes.into())
+                }
+                PeerMessage::BlockHeaders(block_headers) => {
+                    Message_type::BlockHeadersResponse(block_headers.into())
+                }
+                PeerMessage::BlockRequest(block_hash) => {
+                    Message_type::BlockRequest(block_hash.into())
                 }
-                _ => {
-                    unreachable!()
+                PeerMessage::Block(block) => Message_type::BlockResponse(block.into()),
+                PeerMessage::Transaction(transaction) => {
+                    Message_type::Transaction(transaction.into())
+                }
+                PeerMessage::Disconnect(disconnect) => Message_type::Disconnect(disconnect.into()),
+                PeerMessage::Challenge(challenge) => Message_type::Challenge(challenge.into()),
+                PeerMessage::Unknown { unknown_fields, .. } => {
+                    let mut message = Self::default();
+                    *message.special_fields.mut_unknown_fields() = unknown_fields;
+                    return message;
+                }
+                PeerMessage::Routed(routed_message) => {
+                    Message_type::Routed((*routed_message).into())
                 }
             }),
             ..Default::default()
@@ -91,92 +176,80 @@ impl From<PeerMessage> for proto::network::PeerMessage {
 }
 
 impl TryFrom<proto::network::PeerMessage> for PeerMessage {
-    type Error = Box<dyn std::error::Error>;
+    type Error = Error;
 
     fn try_from(value: proto::network::PeerMessage) -> Result<Self, Self::Error> {
-        Ok(match value.message_type.ok_or("no message type")? {
+        let Some(message_type) = value.message_type else {
+            // Message types are length delimited, the lowest field number is the type
+            // in case the unknown type is followed by other unknown fields.
+            let unknown_fields = value.special_fields.unknown_fields();
+            let kind = unknown_fields
+                .iter()
+                .filter(|(_, value)| matches!(value, UnknownValueRef::LengthDelimited(_)))
+                .map(|(number, _)| number)
+                .min()
+                .ok_or(Error::MissingField("message_type"))?;
+
+            return Ok(PeerMessage::Unknown {
+                kind,
+                unknown_fields: unknown_fields.clone(),
+            });
+        };
+
+        Ok(match message_type {
             Message_type::Tier1Handshake(handshake) => {
-                PeerMessage::Tier1Handshake(handshake.try_into().unwrap())
+                PeerMessage::Tier1Handshake(handshake.try_into()?)
             }
             Message_type::Tier2Handshake(handshake) => {
-                PeerMessage::Tier2Handshake(handshake.try_into().unwrap())
+                PeerMessage::Tier2Handshake(handshake.try_into()?)
             }
 
-            Message_type::Routed(message) => PeerMessage::Routed(
-                RoutedMessageV2 {
-                    msg: RoutedMessage::try_from_slice(message.borsh.as_slice())?,
-                    created_at: message
-                        .created_at
-                        .as_ref()
-                        .map(utc_from_proto)
-                        .transpose()?,
-                }
-                .into(),
-            ),
-            Message_type::HandshakeFailure(_) => {
-                println!("<<< Receive HandshakeFailure");
-                Err("skipped message type")?
+            Message_type::Routed(routed_message) => {
+                PeerMessage::Routed(Box::new(routed_message.try_into()?))
             }
-            Message_type::LastEdge(_) => {
-                println!("<<< Receive LastEdge");
-                Err("skipped message type")?
+            Message_type::HandshakeFailure(handshake_failure) => {
+                let (peer_info, reason) = handshake_failure.try_into()?;
+                PeerMessage::HandshakeFailure(peer_info, reason)
             }
-            Message_type::SyncRoutingTable(_) => {
-                println!("<<< Receive SyncRoutingTable");
-                Err("skipped message type")?
+            Message_type::LastEdge(last_edge) => PeerMessage::LastEdge(last_edge.try_into()?),
+            Message_type::SyncRoutingTable(routing_table_update) => {
+                PeerMessage::SyncRoutingTable(routing_table_update.try_into()?)
             }
-            Message_type::DistanceVector(_) => {
-                println!("<<< Receive DistanceVector");
-                Err("skipped message type")?
+            Message_type::DistanceVector(distance_vector) => {
+                PeerMessage::DistanceVector(distance_vector.try_into()?)
             }
-            Message_type::UpdateNonceRequest(_) => {
-                println!("<<< Receive UpdateNonceRequest");
-                Err("skipped message type")?
+            Message_type::UpdateNonceRequest(update_nonce_request) => {
+                PeerMessage::RequestUpdateNonce(update_nonce_request.try_into()?)
             }
-            Message_type::UpdateNonceResponse(_) => {
-                println!("<<< Receive UpdateNonceResponse");
-                Err("skipped message type")?
+            Message_type::UpdateNonceResponse(update_nonce_response) => {
+                PeerMessage::ResponseUpdateNonce(update_nonce_response.try_into()?)
             }
-            Message_type::SyncAccountsData(_) => {
-                println!("<<< Receive SyncAccountsData");
-                Err("skipped message type")?
+            Message_type::SyncAccountsData(sync_accounts_data) => {
+                PeerMessage::SyncAccountsData(sync_accounts_data.try_into()?)
             }
-            Message_type::PeersRequest(_) => {
-                println!("<<< Receive PeersRequest");
-                Err("skipped message type")?
+            Message_type::PeersRequest(peers_request) => {
+                PeerMessage::PeersRequest(peers_request.into())
             }
-            Message_type::PeersResponse(_) => {
-                println!("<<< Receive PeersResponse");
-                Err("skipped message type")?
+            Message_type::PeersResponse(peers_response) => {
+                PeerMessage::PeersResponse(peers_response.try_into()?)
             }
-            Message_type::BlockHeadersRequest(_) => {
-                println!("<<< Receive BlockHeadersRequest");
-                Err("skipped message type")?
+            Message_type::BlockHeadersRequest(block_headers_request) => {
+                PeerMessage::BlockHeadersRequest(block_headers_request.try_into()?)
             }
-            Message_type::BlockHeadersResponse(_) => {
-                println!("<<< Receive BlockHeadersResponse");
-                Err("skipped message type")?
+            Message_type::BlockHeadersResponse(block_headers_response) => {
+                PeerMessage::BlockHeaders(block_headers_response.try_into()?)
             }
-            Message_type::BlockRequest(_) => {
-                println!("<<< Receive BlockRequest");
-                Err("skipped message type")?
+            Message_type::BlockRequest(block_request) => {
+                PeerMessage::BlockRequest(block_request.try_into()?)
             }
-            Message_type::BlockResponse(_) => {
-                println!("<<< Receive BlockResponse");
-                Err("skipped message type")?
+            Message_type::BlockResponse(block_response) => {
+                PeerMessage::Block(block_response.try_into()?)
             }
-            Message_type::Transaction(_) => {
-                println!("<<< Receive Transaction");
-                Err("skipped message type")?
-            }
-            Message_type::Disconnect(_) => {
-                println!("<<< Receive Disconnect");
-                Err("skipped message type")?
-            }
-            Message_type::Challenge(_) => {
-                println!("<<< Receive Challenge");
-                Err("skipped message type")?
+            Message_type::Transaction(transaction) => {
+                PeerMessage::Transaction(transaction.try_into()?)
             }
+            Message_type::Disconnect(disconnect) => PeerMessage::Disconnect(disconnect.into()),
+            Message_type::Challenge(challenge) => PeerMessage::Challenge(challenge.try_into()?),
         })
     }
 }
@@ -202,9 +275,12 @@ mod tests {
     use near_primitives::borsh::BorshDeserialize;
     use near_primitives::hash::CryptoHash;
     use near_primitives::network::PeerId;
+    use protobuf::Message;
     use rand::rngs::OsRng;
 
+    use crate::error::Error;
     use crate::proto::network;
+    use crate::proto::network::peer_message::Message_type;
     use crate::types::handshake::Handshake;
     use crate::types::peer_message::PeerMessage;
 
@@ -242,6 +318,7 @@ mod tests {
             sender_listen_port: Some(51200),
             sender_chain_info,
             partial_edge_info,
+            owned_account: None,
         };
 
         let peer_message = PeerMessage::Tier2Handshake(handshake);
@@ -253,4 +330,58 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_decode_errors() {
+        let error = PeerMessage::try_from(network::PeerMessage::default()).unwrap_err();
+        assert!(matches!(error, Error::MissingField("message_type")));
+
+        let network_peer_message = network::PeerMessage {
+            message_type: Some(Message_type::LastEdge(Default::default())),
+            ..Default::default()
+        };
+        let error = PeerMessage::try_from(network_peer_message).unwrap_err();
+        assert!(matches!(error, Error::MissingField("edge")));
+    }
+
+    #[test]
+    fn test_unknown() -> Result<()> {
+        let mut network_peer_message = network::PeerMessage::default();
+        let unknown_fields = network_peer_message.special_fields.mut_unknown_fields();
+        unknown_fields.add_length_delimited(100, vec![4, 5]);
+        unknown_fields.add_length_delimited(99, vec![1, 2, 3]);
+        unknown_fields.add_varint(101, 6);
+        let bytes = network_peer_message.write_to_bytes()?;
+
+        let peer_message = PeerMessage::parse_from_bytes(&bytes)?;
+        assert_eq!(
+            peer_message,
+            PeerMessage::Unknown {
+                kind: 99,
+                unknown_fields: network_peer_message.special_fields.unknown_fields().clone(),
+            }
+        );
+        assert_eq!(peer_message.clone().write_to_bytes()?, bytes);
+
+        let network_peer_message_restored: network::PeerMessage = peer_message.clone().into();
+        assert_eq!(network_peer_message, network_peer_message_restored);
+        assert_eq!(
+            PeerMessage::try_from(network_peer_message_restored)?,
+            peer_message
+        );
+
+        let error = PeerMessage::parse_from_bytes(&[]).unwrap_err();
+        assert!(matches!(error, Error::MissingField("message_type")));
+
+        // Message types are length delimited.
+        let mut network_peer_message = network::PeerMessage::default();
+        network_peer_message
+            .special_fields
+            .mut_unknown_fields()
+            .add_varint(99, 1);
+        let error = PeerMessage::try_from(network_peer_message).unwrap_err();
+        assert!(matches!(error, Error::MissingField("message_type")));
+
+        Ok(())
+    }
 }
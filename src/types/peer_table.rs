@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use near_network_primitives::types::PeerInfo;
+use near_primitives::network::PeerId;
+
+use crate::types::peers::{PeersRequest, PeersResponse};
+
+/// Peers known to the node, used to answer `PeersRequest`.
+#[derive(Debug, Default)]
+pub struct PeerTable {
+    peers: HashMap<PeerId, PeerInfo>,
+    direct_peers: HashMap<PeerId, PeerInfo>,
+}
+
+impl PeerTable {
+    /// Remembers a peer, keeping the already known address if the new one is missing.
+    pub fn insert(&mut self, peer_info: PeerInfo) {
+        match self.peers.get_mut(&peer_info.id) {
+            Some(known) if peer_info.addr.is_none() => {
+                known.account_id = peer_info.account_id.or(known.account_id.take());
+            }
+            _ => {
+                self.peers.insert(peer_info.id.clone(), peer_info);
+            }
+        }
+    }
+
+    /// Remembers a peer with an established connection.
+    pub fn insert_direct(&mut self, peer_info: PeerInfo) {
+        self.insert(peer_info.clone());
+        self.direct_peers.insert(peer_info.id.clone(), peer_info);
+    }
+
+    /// Forgets the connection to the peer, the peer itself stays known.
+    pub fn remove_direct(&mut self, peer_id: &PeerId) {
+        self.direct_peers.remove(peer_id);
+    }
+
+    pub fn peers(&self) -> impl Iterator<Item = &PeerInfo> {
+        self.peers.values()
+    }
+
+    pub fn direct_peers(&self) -> impl Iterator<Item = &PeerInfo> {
+        self.direct_peers.values()
+    }
+
+    /// Answers `PeersRequest` within the requested limits.
+    pub fn peers_response(&self, request: &PeersRequest) -> PeersResponse {
+        let limit = |max: Option<u32>| max.map_or(usize::MAX, |max| max as usize);
+
+        PeersResponse {
+            peers: self
+                .peers()
+                .take(limit(request.max_peers))
+                .cloned()
+                .collect(),
+            direct_peers: self
+                .direct_peers()
+                .take(limit(request.max_direct_peers))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use near_crypto::{ED25519PublicKey, PublicKey};
+    use near_network_primitives::types::PeerInfo;
+    use near_primitives::network::PeerId;
+    use rand::rngs::OsRng;
+
+    use crate::types::peer_table::PeerTable;
+    use crate::types::peers::PeersRequest;
+
+    #[test]
+    fn test_peers_response() {
+        let peer_info = |port| PeerInfo {
+            id: PeerId::new(PublicKey::ED25519(ED25519PublicKey::from(
+                ed25519_dalek::SecretKey::generate(&mut OsRng).to_bytes(),
+            ))),
+            addr: Some(SocketAddr::from((Ipv4Addr::LOCALHOST, port))),
+            account_id: None,
+        };
+        let (first, second, third) = (peer_info(24567), peer_info(24568), peer_info(24569));
+
+        let mut peer_table = PeerTable::default();
+        peer_table.insert(first.clone());
+        peer_table.insert_direct(second.clone());
+        peer_table.insert_direct(third.clone());
+        peer_table.remove_direct(&third.id);
+
+        // Known address isn't lost.
+        peer_table.insert(PeerInfo {
+            addr: None,
+            ..first.clone()
+        });
+
+        let response = peer_table.peers_response(&PeersRequest::default());
+        assert_eq!(response.peers.len(), 3);
+        assert!(response.peers.contains(&first));
+        assert_eq!(response.direct_peers, vec![second]);
+
+        let response = peer_table.peers_response(&PeersRequest {
+            max_peers: Some(1),
+            max_direct_peers: Some(0),
+        });
+        assert_eq!(response.peers.len(), 1);
+        assert!(response.direct_peers.is_empty());
+    }
+}
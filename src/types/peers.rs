@@ -0,0 +1,114 @@
+use near_network_primitives::types::PeerInfo;
+
+use crate::error::Error;
+use crate::proto;
+
+/// Request for the list of known healthy peers.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct PeersRequest {
+    /// Limits the number of peers to send back.
+    pub max_peers: Option<u32>,
+    /// Limits the number of direct peers to send back.
+    pub max_direct_peers: Option<u32>,
+}
+
+/// Response to `PeersRequest`.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct PeersResponse {
+    /// Peers known to the responding node, including ones learned from other peers.
+    pub peers: Vec<PeerInfo>,
+    /// Peers directly connected to the responding node.
+    pub direct_peers: Vec<PeerInfo>,
+}
+
+impl From<PeersRequest> for proto::network::PeersRequest {
+    fn from(value: PeersRequest) -> Self {
+        Self {
+            max_peers: value.max_peers,
+            max_direct_peers: value.max_direct_peers,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<proto::network::PeersRequest> for PeersRequest {
+    fn from(value: proto::network::PeersRequest) -> Self {
+        Self {
+            max_peers: value.max_peers,
+            max_direct_peers: value.max_direct_peers,
+        }
+    }
+}
+
+impl From<PeersResponse> for proto::network::PeersResponse {
+    fn from(value: PeersResponse) -> Self {
+        Self {
+            peers: value.peers.into_iter().map(Into::into).collect(),
+            direct_peers: value.direct_peers.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::PeersResponse> for PeersResponse {
+    type Error = Error;
+
+    fn try_from(value: proto::network::PeersResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            peers: value
+                .peers
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            direct_peers: value
+                .direct_peers
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use anyhow::Result;
+    use near_crypto::{ED25519PublicKey, PublicKey};
+    use near_network_primitives::types::PeerInfo;
+    use near_primitives::network::PeerId;
+    use rand::rngs::OsRng;
+
+    use crate::proto::network;
+    use crate::types::peers::{PeersRequest, PeersResponse};
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let peers_request = PeersRequest {
+            max_peers: Some(100),
+            max_direct_peers: None,
+        };
+        let peers_request_original = peers_request.clone();
+        let network_peers_request: network::PeersRequest = peers_request.into();
+        let peers_request_restored: PeersRequest = network_peers_request.into();
+        assert_eq!(peers_request_original, peers_request_restored);
+
+        let peer_info = |port| PeerInfo {
+            id: PeerId::new(PublicKey::ED25519(ED25519PublicKey::from(
+                ed25519_dalek::SecretKey::generate(&mut OsRng).to_bytes(),
+            ))),
+            addr: Some(SocketAddr::from((Ipv4Addr::LOCALHOST, port))),
+            account_id: None,
+        };
+        let peers_response = PeersResponse {
+            peers: vec![peer_info(24567), peer_info(24568)],
+            direct_peers: vec![peer_info(24569)],
+        };
+        let peers_response_original = peers_response.clone();
+        let network_peers_response: network::PeersResponse = peers_response.into();
+        let peers_response_restored: PeersResponse = network_peers_response.try_into()?;
+        assert_eq!(peers_response_original, peers_response_restored);
+
+        Ok(())
+    }
+}
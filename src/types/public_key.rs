@@ -1,6 +1,7 @@
 use near_crypto::PublicKey;
 use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
 
+use crate::error::Error;
 use crate::proto;
 
 impl From<PublicKey> for proto::network::PublicKey {
@@ -13,10 +14,10 @@ impl From<PublicKey> for proto::network::PublicKey {
 }
 
 impl TryFrom<proto::network::PublicKey> for PublicKey {
-    type Error = std::io::Error;
+    type Error = Error;
 
     fn try_from(value: proto::network::PublicKey) -> Result<Self, Self::Error> {
-        Self::try_from_slice(value.borsh.as_slice())
+        Self::try_from_slice(value.borsh.as_slice()).map_err(Error::Borsh)
     }
 }
 
@@ -25,10 +26,11 @@ mod tests {
     use near_crypto::{ED25519PublicKey, PublicKey};
     use rand::rngs::OsRng;
 
+    use crate::error::Error;
     use crate::proto::network;
 
     #[test]
-    fn test_serde() -> std::io::Result<()> {
+    fn test_serde() -> Result<(), Error> {
         let pk = PublicKey::ED25519(ED25519PublicKey::from(
             ed25519_dalek::SecretKey::generate(&mut OsRng).to_bytes(),
         ));
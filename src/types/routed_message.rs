@@ -0,0 +1,151 @@
+use near_network_primitives::time::Utc;
+use near_network_primitives::types::{RoutedMessage as SignedRoutedMessage, RoutedMessageV2};
+use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
+use protobuf::MessageField;
+
+use crate::error::Error;
+use crate::proto;
+use crate::types::peer_message::{utc_from_proto, utc_to_proto};
+
+/// Message routed through the network to `msg.target`, signed by `msg.author`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct RoutedMessage {
+    pub msg: SignedRoutedMessage,
+    pub created_at: Option<Utc>,
+    /// Number of peers the message went through.
+    pub num_hops: u32,
+}
+
+impl RoutedMessage {
+    pub fn verify(&self) -> bool {
+        self.msg.verify()
+    }
+
+    /// TTL of a message is decremented on every hop, expired messages are dropped.
+    pub fn is_expired(&self) -> bool {
+        self.msg.ttl == 0
+    }
+
+    /// Decrements the TTL and increments the hop count before the message is sent
+    /// to the next peer. Returns false if the TTL expired and the message must be dropped.
+    pub fn forward(&mut self) -> bool {
+        self.num_hops = self.num_hops.saturating_add(1);
+        self.msg.decrease_ttl()
+    }
+}
+
+impl From<RoutedMessageV2> for RoutedMessage {
+    fn from(value: RoutedMessageV2) -> Self {
+        Self {
+            msg: value.msg,
+            created_at: value.created_at,
+            num_hops: 0,
+        }
+    }
+}
+
+impl From<RoutedMessage> for proto::network::RoutedMessage {
+    fn from(value: RoutedMessage) -> Self {
+        Self {
+            borsh: value.msg.try_to_vec().unwrap(),
+            created_at: MessageField::from_option(value.created_at.as_ref().map(utc_to_proto)),
+            num_hops: Some(value.num_hops.try_into().unwrap_or(i32::MAX)),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::RoutedMessage> for RoutedMessage {
+    type Error = Error;
+
+    fn try_from(value: proto::network::RoutedMessage) -> Result<Self, Self::Error> {
+        let routed_message = Self {
+            msg: SignedRoutedMessage::try_from_slice(value.borsh.as_slice())
+                .map_err(Error::Borsh)?,
+            created_at: value
+                .created_at
+                .as_ref()
+                .map(utc_from_proto)
+                .transpose()
+                .map_err(|_| Error::InvalidField("created_at"))?,
+            num_hops: value
+                .num_hops
+                .unwrap_or_default()
+                .try_into()
+                .map_err(|_| Error::InvalidField("num_hops"))?,
+        };
+
+        if routed_message.is_expired() {
+            return Err(Error::TtlExpired);
+        }
+
+        Ok(routed_message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_crypto::{ED25519SecretKey, SecretKey};
+    use near_network_primitives::time;
+    use near_network_primitives::types::{
+        AccountOrPeerIdOrHash, Ping, RawRoutedMessage, RoutedMessageBody,
+    };
+    use near_primitives::network::PeerId;
+    use rand::rngs::OsRng;
+
+    use crate::error::Error;
+    use crate::proto::network;
+    use crate::types::routed_message::RoutedMessage;
+
+    fn ping(ttl: u8) -> RoutedMessage {
+        let keypair = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let secret_key = SecretKey::ED25519(ED25519SecretKey(keypair.to_bytes()));
+        let source = PeerId::new(secret_key.public_key());
+
+        RawRoutedMessage {
+            target: AccountOrPeerIdOrHash::PeerId(source.clone()),
+            body: RoutedMessageBody::Ping(Ping { nonce: 1, source }),
+        }
+        .sign(&secret_key, ttl, Some(time::Utc::now_utc()))
+        .into()
+    }
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let routed_message = RoutedMessage {
+            num_hops: 3,
+            ..ping(100)
+        };
+
+        let network_routed_message: network::RoutedMessage = routed_message.clone().into();
+        let routed_message_restored: RoutedMessage = network_routed_message.try_into()?;
+        assert_eq!(routed_message, routed_message_restored);
+
+        let network_routed_message = network::RoutedMessage {
+            num_hops: Some(-1),
+            ..routed_message.into()
+        };
+        let error = RoutedMessage::try_from(network_routed_message).unwrap_err();
+        assert!(matches!(error, Error::InvalidField("num_hops")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_forward() {
+        let mut routed_message = ping(2);
+
+        assert!(routed_message.forward());
+        assert_eq!((routed_message.msg.ttl, routed_message.num_hops), (1, 1));
+        assert!(routed_message.verify());
+
+        assert!(!routed_message.forward());
+        assert_eq!((routed_message.msg.ttl, routed_message.num_hops), (0, 2));
+        assert!(routed_message.is_expired());
+
+        let network_routed_message: network::RoutedMessage = routed_message.into();
+        let error = RoutedMessage::try_from(network_routed_message).unwrap_err();
+        assert!(matches!(error, Error::TtlExpired));
+    }
+}
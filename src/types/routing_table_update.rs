@@ -0,0 +1,102 @@
+use near_network_primitives::types::Edge;
+use near_primitives::network::AnnounceAccount;
+
+use crate::error::Error;
+use crate::proto;
+
+/// Part of the routing table gossiped between peers.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct RoutingTableUpdate {
+    /// Edges of the network graph known to the sender.
+    pub edges: Vec<Edge>,
+    /// Accounts announced by validators with the peers they're reachable through.
+    pub accounts: Vec<AnnounceAccount>,
+}
+
+impl From<RoutingTableUpdate> for proto::network::RoutingTableUpdate {
+    fn from(value: RoutingTableUpdate) -> Self {
+        Self {
+            edges: value.edges.into_iter().map(Into::into).collect(),
+            accounts: value.accounts.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::RoutingTableUpdate> for RoutingTableUpdate {
+    type Error = Error;
+
+    fn try_from(value: proto::network::RoutingTableUpdate) -> Result<Self, Self::Error> {
+        Ok(Self {
+            edges: value
+                .edges
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            accounts: value
+                .accounts
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, SecretKey};
+    use near_network_primitives::types::{Edge, PartialEdgeInfo};
+    use near_primitives::network::{AnnounceAccount, PeerId};
+    use near_primitives::types::EpochId;
+    use rand::rngs::OsRng;
+
+    use crate::proto::network;
+    use crate::types::routing_table_update::RoutingTableUpdate;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let keypair0 = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let keypair1 = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let peer0 = PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+            keypair0.public.to_bytes(),
+        )));
+        let peer1 = PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+            keypair1.public.to_bytes(),
+        )));
+        let secret_key0 = SecretKey::ED25519(ED25519SecretKey(keypair0.to_bytes()));
+        let secret_key1 = SecretKey::ED25519(ED25519SecretKey(keypair1.to_bytes()));
+        let partial_edge_info = PartialEdgeInfo::new(&peer1, &peer0, 1, &secret_key1);
+        let edge = Edge::build_with_secret_key(
+            peer0.clone(),
+            peer1,
+            1,
+            &secret_key0,
+            partial_edge_info.signature,
+        );
+
+        let account_id = "test.near".parse()?;
+        let epoch_id = EpochId::default();
+        let signature = secret_key0
+            .sign(AnnounceAccount::build_header_hash(&account_id, &peer0, &epoch_id).as_ref());
+        let announce_account = AnnounceAccount {
+            account_id,
+            peer_id: peer0,
+            epoch_id,
+            signature,
+        };
+
+        let routing_table_update = RoutingTableUpdate {
+            edges: vec![edge],
+            accounts: vec![announce_account],
+        };
+
+        let routing_table_update_original = routing_table_update.clone();
+        let network_routing_table_update: network::RoutingTableUpdate = routing_table_update.into();
+        let routing_table_update_restored: RoutingTableUpdate =
+            network_routing_table_update.try_into()?;
+        assert_eq!(routing_table_update_original, routing_table_update_restored);
+
+        Ok(())
+    }
+}
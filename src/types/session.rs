@@ -0,0 +1,15 @@
+use near_network_primitives::types::{Edge, PeerChainInfoV2};
+use near_primitives::network::PeerId;
+
+/// Parameters of a connection agreed on during the handshake.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Session {
+    /// Peer id of the other side of the connection.
+    pub peer_id: PeerId,
+    /// Newest protocol version supported by both peers.
+    pub protocol_version: u32,
+    /// Chain information announced by the other side.
+    pub peer_chain_info: PeerChainInfoV2,
+    /// Edge of the connection signed by both peers.
+    pub edge: Edge,
+}
@@ -0,0 +1,46 @@
+use near_crypto::Signature;
+use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::error::Error;
+use crate::proto;
+
+impl From<Signature> for proto::network::Signature {
+    fn from(value: Signature) -> Self {
+        Self {
+            borsh: value.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::Signature> for Signature {
+    type Error = Error;
+
+    fn try_from(value: proto::network::Signature) -> Result<Self, Self::Error> {
+        Self::try_from_slice(&value.borsh).map_err(Error::Borsh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_crypto::{ED25519SecretKey, SecretKey, Signature};
+    use rand::rngs::OsRng;
+
+    use crate::proto::network;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let secret_key = SecretKey::ED25519(ED25519SecretKey(
+            ed25519_dalek::Keypair::generate(&mut OsRng).to_bytes(),
+        ));
+        let signature = secret_key.sign(b"payload");
+
+        let signature_original = signature.clone();
+        let network_signature: network::Signature = signature.into();
+        let signature_restored: Signature = network_signature.try_into()?;
+        assert_eq!(signature_original, signature_restored);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::Error;
+use crate::proto;
+use crate::types::account_key::SignedAccountData;
+
+/// Gossip of validators' `AccountData`.
+///
+/// Represents an incremental sync, a full sync request or a full sync response.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct SyncAccountsData {
+    pub accounts_data: Vec<SignedAccountData>,
+    /// Incremental sync if set, full sync otherwise.
+    pub incremental: bool,
+    /// Sender asks for a full sync in return.
+    pub requesting_full_sync: bool,
+}
+
+impl SyncAccountsData {
+    pub fn incremental_sync(accounts_data: Vec<SignedAccountData>) -> Self {
+        Self {
+            accounts_data,
+            incremental: true,
+            requesting_full_sync: false,
+        }
+    }
+
+    pub fn full_sync_request() -> Self {
+        Self {
+            accounts_data: vec![],
+            incremental: false,
+            requesting_full_sync: true,
+        }
+    }
+
+    pub fn full_sync_response(accounts_data: Vec<SignedAccountData>) -> Self {
+        Self {
+            accounts_data,
+            incremental: false,
+            requesting_full_sync: false,
+        }
+    }
+}
+
+impl From<SyncAccountsData> for proto::network::SyncAccountsData {
+    fn from(value: SyncAccountsData) -> Self {
+        Self {
+            accounts_data: value.accounts_data.into_iter().map(Into::into).collect(),
+            incremental: value.incremental,
+            requesting_full_sync: value.requesting_full_sync,
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::SyncAccountsData> for SyncAccountsData {
+    type Error = Error;
+
+    /// Fails if any of the accounts data isn't signed with its account key.
+    fn try_from(value: proto::network::SyncAccountsData) -> Result<Self, Self::Error> {
+        Ok(Self {
+            accounts_data: value
+                .accounts_data
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            incremental: value.incremental,
+            requesting_full_sync: value.requesting_full_sync,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, SecretKey};
+    use near_network_primitives::time;
+    use near_primitives::network::PeerId;
+    use rand::rngs::OsRng;
+
+    use crate::proto::network;
+    use crate::types::account_key::AccountData;
+    use crate::types::sync_accounts_data::SyncAccountsData;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let account_keypair = ed25519_dalek::Keypair::generate(&mut OsRng);
+        let account_secret_key = SecretKey::ED25519(ED25519SecretKey(account_keypair.to_bytes()));
+        let account_data = AccountData {
+            peer_id: PeerId::new(PublicKey::ED25519(ED25519PublicKey(
+                ed25519_dalek::Keypair::generate(&mut OsRng)
+                    .public
+                    .to_bytes(),
+            ))),
+            account_key: account_secret_key.public_key(),
+            proxies: vec![],
+            version: 1,
+            timestamp: time::Utc::now_utc(),
+        }
+        .sign(&account_secret_key);
+
+        for sync_accounts_data in [
+            SyncAccountsData::incremental_sync(vec![account_data.clone()]),
+            SyncAccountsData::full_sync_request(),
+            SyncAccountsData::full_sync_response(vec![account_data]),
+        ] {
+            let sync_accounts_data_original = sync_accounts_data.clone();
+            let network_sync_accounts_data: network::SyncAccountsData = sync_accounts_data.into();
+            let sync_accounts_data_restored: SyncAccountsData =
+                network_sync_accounts_data.try_into()?;
+            assert_eq!(sync_accounts_data_original, sync_accounts_data_restored);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+use opentelemetry::Context;
+
+use crate::error::Error;
+use crate::proto;
+use crate::proto::network::trace_context;
+
+/// Sampling decision of the trace, as propagated by nearcore.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SamplingPriority {
+    AutoReject,
+    UserReject,
+    AutoKeep,
+    UserKeep,
+}
+
+/// OpenTelemetry span of the sender, carried in the `PeerMessage` envelope
+/// to correlate spans of both nodes.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TraceContext {
+    pub trace_id: TraceId,
+    pub span_id: SpanId,
+    pub sampling_priority: Option<SamplingPriority>,
+}
+
+impl TraceContext {
+    /// Context of the current OpenTelemetry span, `None` outside of a valid span.
+    pub fn current() -> Option<Self> {
+        Self::from_span_context(Context::current().span().span_context())
+    }
+
+    pub fn from_span_context(span_context: &SpanContext) -> Option<Self> {
+        span_context.is_valid().then(|| Self {
+            trace_id: span_context.trace_id(),
+            span_id: span_context.span_id(),
+            sampling_priority: Some(if span_context.is_sampled() {
+                SamplingPriority::AutoKeep
+            } else {
+                SamplingPriority::AutoReject
+            }),
+        })
+    }
+
+    /// Remote span context of the sender.
+    pub fn span_context(&self) -> SpanContext {
+        let trace_flags = match self.sampling_priority {
+            Some(SamplingPriority::AutoKeep | SamplingPriority::UserKeep) => TraceFlags::SAMPLED,
+            _ => TraceFlags::default(),
+        };
+
+        SpanContext::new(
+            self.trace_id,
+            self.span_id,
+            trace_flags,
+            true,
+            TraceState::default(),
+        )
+    }
+
+    /// Current OpenTelemetry context with the sender's span as the parent of new spans.
+    pub fn context(&self) -> Context {
+        Context::current().with_remote_span_context(self.span_context())
+    }
+}
+
+impl From<TraceContext> for proto::network::TraceContext {
+    fn from(value: TraceContext) -> Self {
+        Self {
+            trace_id: value.trace_id.to_bytes().to_vec(),
+            span_id: value.span_id.to_bytes().to_vec(),
+            sampling_priority: match value.sampling_priority {
+                None => trace_context::SamplingPriority::UNKNOWN,
+                Some(SamplingPriority::AutoReject) => trace_context::SamplingPriority::AutoReject,
+                Some(SamplingPriority::UserReject) => trace_context::SamplingPriority::UserReject,
+                Some(SamplingPriority::AutoKeep) => trace_context::SamplingPriority::AutoKeep,
+                Some(SamplingPriority::UserKeep) => trace_context::SamplingPriority::UserKeep,
+            }
+            .into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::TraceContext> for TraceContext {
+    type Error = Error;
+
+    fn try_from(value: proto::network::TraceContext) -> Result<Self, Self::Error> {
+        Ok(Self {
+            trace_id: TraceId::from_bytes(
+                value
+                    .trace_id
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| Error::InvalidField("trace_id"))?,
+            ),
+            span_id: SpanId::from_bytes(
+                value
+                    .span_id
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| Error::InvalidField("span_id"))?,
+            ),
+            sampling_priority: match value.sampling_priority.enum_value() {
+                Ok(trace_context::SamplingPriority::AutoReject) => {
+                    Some(SamplingPriority::AutoReject)
+                }
+                Ok(trace_context::SamplingPriority::UserReject) => {
+                    Some(SamplingPriority::UserReject)
+                }
+                Ok(trace_context::SamplingPriority::AutoKeep) => Some(SamplingPriority::AutoKeep),
+                Ok(trace_context::SamplingPriority::UserKeep) => Some(SamplingPriority::UserKeep),
+                Ok(trace_context::SamplingPriority::UNKNOWN) | Err(_) => None,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use opentelemetry::trace::{SpanId, TraceId};
+    use opentelemetry::Context;
+
+    use crate::error::Error;
+    use crate::proto::network;
+    use crate::types::trace_context::{SamplingPriority, TraceContext};
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let trace_context = TraceContext {
+            trace_id: TraceId::from_bytes(rand::random()),
+            span_id: SpanId::from_bytes(rand::random()),
+            sampling_priority: Some(SamplingPriority::UserKeep),
+        };
+
+        let network_trace_context: network::TraceContext = trace_context.into();
+        let trace_context_restored: TraceContext = network_trace_context.try_into()?;
+        assert_eq!(trace_context, trace_context_restored);
+
+        let network_trace_context = network::TraceContext {
+            trace_id: vec![1; 8],
+            ..trace_context.into()
+        };
+        let error = TraceContext::try_from(network_trace_context).unwrap_err();
+        assert!(matches!(error, Error::InvalidField("trace_id")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_current() {
+        assert_eq!(TraceContext::current(), None);
+
+        let trace_context = TraceContext {
+            trace_id: TraceId::from_bytes(rand::random()),
+            span_id: SpanId::from_bytes(rand::random()),
+            sampling_priority: Some(SamplingPriority::AutoKeep),
+        };
+        let _guard = trace_context.context().attach();
+        assert_eq!(TraceContext::current(), Some(trace_context));
+
+        let _guard = Context::new().attach();
+        assert_eq!(TraceContext::current(), None);
+    }
+}
@@ -0,0 +1,52 @@
+use near_primitives::borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::transaction::SignedTransaction;
+
+use crate::error::Error;
+use crate::proto;
+
+impl From<SignedTransaction> for proto::network::SignedTransaction {
+    fn from(value: SignedTransaction) -> Self {
+        Self {
+            borsh: value.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<proto::network::SignedTransaction> for SignedTransaction {
+    type Error = Error;
+
+    fn try_from(value: proto::network::SignedTransaction) -> Result<Self, Self::Error> {
+        Self::try_from_slice(&value.borsh).map_err(Error::Borsh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use near_crypto::{InMemorySigner, KeyType};
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::transaction::SignedTransaction;
+
+    use crate::proto::network;
+
+    #[test]
+    fn test_serde() -> Result<()> {
+        let signer = InMemorySigner::from_seed("alice.near".parse()?, KeyType::ED25519, "alice");
+        let transaction = SignedTransaction::send_money(
+            1,
+            "alice.near".parse()?,
+            "bob.near".parse()?,
+            &signer,
+            100,
+            CryptoHash::default(),
+        );
+
+        let transaction_original = transaction.clone();
+        let network_transaction: network::SignedTransaction = transaction.into();
+        let transaction_restored: SignedTransaction = network_transaction.try_into()?;
+        assert_eq!(transaction_original, transaction_restored);
+
+        Ok(())
+    }
+}